@@ -14,7 +14,7 @@ import { SafeAreaView } from "react-native-safe-area-context";
 import Svg, { Circle } from "react-native-svg";
 // import PROFILE_IMAGE from "./../../assets/icons/profile.png";
 import * as ImagePicker from "expo-image-picker";
-import { getMyProfile, getUploadUrl } from "@/lib/api/endpoints";
+import { confirmUpload, getMyProfile, getUploadUrl } from "@/lib/api/endpoints";
 
 const PROFILE_IMAGE = {
   uri: "https://upload.wikimedia.org/wikipedia/commons/8/89/Portrait_Placeholder.png",
@@ -60,7 +60,8 @@ export default function Profile() {
       const mimeType = extension === "png" ? "image/png" : "image/jpeg";
 
       // Get signed upload URL from backend
-      const { upload_url, key } = await getUploadUrl(fileName, mimeType);
+      // Replaces the main photo (slot 0)
+      const { upload_url, key, image_id } = await getUploadUrl(fileName, mimeType, 0);
 
       // Read file and upload to R2
       const response = await fetch(imageUri);
@@ -77,6 +78,7 @@ export default function Profile() {
       if (!uploadResponse.ok) {
         throw new Error("Failed to upload to R2");
       }
+      await confirmUpload(image_id);
 
       console.log("Image uploaded successfully, key:", key);
       return key;
@@ -1,4 +1,4 @@
-import { getUploadUrl, getMyProfile } from "@/lib/api/endpoints";
+import { confirmUpload, getUploadUrl, getMyProfile } from "@/lib/api/endpoints";
 import { Ionicons } from "@expo/vector-icons";
 import * as ImagePicker from "expo-image-picker";
 import { useRouter } from "expo-router";
@@ -92,7 +92,7 @@ export default function ImagesScreen() {
             const contentType = filename.endsWith(".png") ? "image/png" : "image/jpeg";
 
             // Get presigned upload URL
-            const { upload_url, key } = await getUploadUrl(filename, contentType);
+            const { upload_url, key, image_id } = await getUploadUrl(filename, contentType, slotIndex);
 
             // Upload the file to R2
             const response = await fetch(uri);
@@ -105,6 +105,7 @@ export default function ImagesScreen() {
                 },
                 body: blob,
             });
+            await confirmUpload(image_id);
 
             // Update slot with success
             newSlots[slotIndex] = { uri, isUploading: false, uploadedKey: key };
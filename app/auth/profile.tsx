@@ -1,4 +1,4 @@
-import { updateProfile } from "@/lib/api/endpoints";
+import { GeoLocation, updateProfile } from "@/lib/api/endpoints";
 import { Ionicons } from "@expo/vector-icons";
 import { useRouter } from "expo-router";
 import { useRef, useState } from "react";
@@ -53,6 +53,13 @@ const OptionPicker = ({
   </View>
 );
 
+// "lat, lng" typed by the user -> coordinates for the API (undefined if not valid)
+const parseLocation = (text: string): GeoLocation | undefined => {
+  const [lat, lng] = text.split(",").map((part) => parseFloat(part.trim()));
+  if (Number.isNaN(lat) || Number.isNaN(lng)) return undefined;
+  return { lat, lng };
+};
+
 export default function ProfileSetupScreen() {
   const router = useRouter();
   const [isSubmitting, setIsSubmitting] = useState(false);
@@ -124,7 +131,7 @@ export default function ProfileSetupScreen() {
         gender: gender || undefined,
         sexuality: sexuality || undefined,
         height: height ? parseInt(height) : undefined,
-        location: location || undefined,
+        location: parseLocation(location),
         job: job || undefined,
         company: company || undefined,
         school: school || undefined,
@@ -302,7 +309,7 @@ export default function ProfileSetupScreen() {
                 <View style={styles.inputGroup}>
                   <TextInput
                     style={styles.input}
-                    placeholder="Location (City)"
+                    placeholder="Location (lat, lng)"
                     placeholderTextColor="#ccc"
                     value={location}
                     onChangeText={setLocation}
@@ -0,0 +1,4 @@
+-- Unread counters only look at messages that are still unread
+CREATE INDEX IF NOT EXISTS idx_messages_unread
+ON messages(match_id, sender_id)
+WHERE is_read = FALSE;
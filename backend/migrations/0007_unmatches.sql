@@ -0,0 +1,13 @@
+-- Pairs that unmatched: the match row and its messages are deleted,
+-- this keeps the pair out of each other's feed and records why for analytics
+CREATE TABLE IF NOT EXISTS unmatches (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user1_id UUID REFERENCES users(id) ON DELETE CASCADE, -- smaller id of the pair
+    user2_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    unmatched_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE(user1_id, user2_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_unmatches_user2 ON unmatches(user2_id);
@@ -0,0 +1,24 @@
+-- Blocks: a block hides both users from each other everywhere (feed, likes, matches)
+CREATE TABLE IF NOT EXISTS blocks (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    blocker_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    blocked_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE(blocker_id, blocked_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_blocks_blocked ON blocks(blocked_id);
+
+-- Reports on a profile or one of its parts, reviewed by moderation
+CREATE TABLE IF NOT EXISTS reports (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    reporter_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    reported_user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    target_type VARCHAR(20) NOT NULL, -- 'PROFILE', 'PHOTO', 'PROMPT', 'MESSAGE'
+    target_id UUID, -- NULL for PROFILE
+    category VARCHAR(50) NOT NULL,
+    details TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_reports_reported_user ON reports(reported_user_id);
@@ -0,0 +1,58 @@
+-- Normalized copies of users.preferences, kept in sync by Postgres, so the feed can
+-- check the candidates' preferences against the viewer (reciprocal matching)
+-- without parsing JSON for every row
+
+-- JSON array of strings -> TEXT[]; NULL when missing or empty (= open to all)
+CREATE OR REPLACE FUNCTION preference_text_array(value JSONB) RETURNS TEXT[]
+LANGUAGE SQL IMMUTABLE AS $$
+    SELECT CASE
+        WHEN jsonb_typeof(value) = 'array' AND jsonb_array_length(value) > 0
+        THEN ARRAY(SELECT jsonb_array_elements_text(value))
+    END
+$$;
+
+-- JSON number or numeric string -> INT; NULL for anything else ("", "abc", 12.5, too large),
+-- so a malformed value in old rows can't make the migration or a later update fail
+CREATE OR REPLACE FUNCTION preference_int(value JSONB) RETURNS INT
+LANGUAGE SQL IMMUTABLE AS $$
+    SELECT CASE
+        WHEN jsonb_typeof(value) IN ('number', 'string')
+            AND value #>> '{}' ~ '^\s*-?\d{1,9}(\.0*)?\s*$'
+        THEN (value #>> '{}')::NUMERIC::INT
+    END
+$$;
+
+-- JSON boolean or "true"/"false" -> BOOLEAN; NULL for anything else
+CREATE OR REPLACE FUNCTION preference_bool(value JSONB) RETURNS BOOLEAN
+LANGUAGE SQL IMMUTABLE AS $$
+    SELECT CASE
+        WHEN jsonb_typeof(value) = 'boolean' THEN value::TEXT::BOOLEAN
+        WHEN jsonb_typeof(value) = 'string' AND lower(value #>> '{}') IN ('true', 'false')
+        THEN (value #>> '{}')::BOOLEAN
+    END
+$$;
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS pref_age_min INT
+        GENERATED ALWAYS AS (preference_int(preferences -> 'ageRange' -> 'min')) STORED,
+    ADD COLUMN IF NOT EXISTS pref_age_max INT
+        GENERATED ALWAYS AS (preference_int(preferences -> 'ageRange' -> 'max')) STORED,
+    ADD COLUMN IF NOT EXISTS pref_distance_max INT
+        GENERATED ALWAYS AS (preference_int(preferences -> 'distanceMax')) STORED,
+    ADD COLUMN IF NOT EXISTS pref_genders TEXT[]
+        GENERATED ALWAYS AS (preference_text_array(preferences -> 'genderPreference')) STORED,
+    -- only dealbreakers, nice-to-have preferences never exclude anyone
+    ADD COLUMN IF NOT EXISTS pref_ethnicities TEXT[]
+        GENERATED ALWAYS AS (
+            CASE WHEN COALESCE(preference_bool(preferences -> 'ethnicityDealbreaker'), TRUE)
+                THEN preference_text_array(preferences -> 'ethnicityPreference')
+            END
+        ) STORED,
+    ADD COLUMN IF NOT EXISTS pref_religions TEXT[]
+        GENERATED ALWAYS AS (
+            CASE WHEN COALESCE(preference_bool(preferences -> 'religionDealbreaker'), TRUE)
+                THEN preference_text_array(preferences -> 'religionPreference')
+            END
+        ) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_users_pref_genders ON users USING GIN(pref_genders);
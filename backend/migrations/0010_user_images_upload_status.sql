@@ -0,0 +1,12 @@
+-- Images are 'pending' until POST /profile/images/{id}/confirm finds the object in
+-- storage, then 'ready'. Rows from before the confirm step were shown all along,
+-- so they count as ready
+UPDATE user_images SET upload_status = 'ready' WHERE upload_status IS DISTINCT FROM 'ready';
+
+ALTER TABLE user_images
+    ALTER COLUMN upload_status SET NOT NULL,
+    ADD CONSTRAINT user_images_upload_status_check CHECK (upload_status IN ('pending', 'ready'));
+
+-- The reaper looks for pending uploads older than the TTL
+CREATE INDEX IF NOT EXISTS idx_user_images_pending ON user_images(created_at)
+    WHERE upload_status = 'pending';
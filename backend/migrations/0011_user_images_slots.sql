@@ -0,0 +1,18 @@
+-- Profile photos live in 6 slots (display_order 0 to 5). Each slot holds at most one
+-- shown ('ready') image and one upload in progress ('pending') that replaces it on confirm
+
+-- display_order used to be a count, so concurrent uploads could share one: compact them
+UPDATE user_images ui
+SET display_order = ranked.slot
+FROM (
+    SELECT id, ROW_NUMBER() OVER (
+        PARTITION BY user_id, upload_status ORDER BY display_order, created_at, id
+    ) - 1 AS slot
+    FROM user_images
+) ranked
+WHERE ui.id = ranked.id AND ui.display_order <> ranked.slot;
+
+-- Deferrable so a reorder can swap images inside one transaction
+ALTER TABLE user_images
+    ADD CONSTRAINT user_images_slot_key UNIQUE (user_id, display_order, upload_status)
+        DEFERRABLE INITIALLY IMMEDIATE;
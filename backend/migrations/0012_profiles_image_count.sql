@@ -0,0 +1,6 @@
+-- profiles.image_count was never maintained, it now tracks the ready images
+UPDATE profiles p
+SET image_count = (
+    SELECT COUNT(*) FROM user_images ui
+    WHERE ui.user_id = p.user_id AND ui.upload_status = 'ready'
+);
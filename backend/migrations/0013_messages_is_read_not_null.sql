@@ -0,0 +1,7 @@
+-- is_read was nullable, so the unread queries had to COALESCE it and could not use
+-- idx_messages_unread (WHERE is_read = FALSE). NULL always meant unread
+UPDATE messages SET is_read = FALSE WHERE is_read IS NULL;
+
+ALTER TABLE messages
+    ALTER COLUMN is_read SET DEFAULT FALSE,
+    ALTER COLUMN is_read SET NOT NULL;
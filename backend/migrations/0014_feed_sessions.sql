@@ -0,0 +1,18 @@
+-- The ranked candidates of a feed session. They are scored once when the session starts,
+-- so every page walks the same order even though last_active (a ranking input) keeps moving
+CREATE TABLE IF NOT EXISTS feed_session_candidates (
+    session_id UUID NOT NULL,
+    viewer_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    candidate_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    score FLOAT8 NOT NULL,
+    sort_hash TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (session_id, candidate_id)
+);
+
+-- Keyset paging in ranked order (score DESC, then the session shuffle)
+CREATE INDEX IF NOT EXISTS idx_feed_session_candidates_order
+ON feed_session_candidates(session_id, score DESC, sort_hash);
+
+CREATE INDEX IF NOT EXISTS idx_feed_session_candidates_viewer
+ON feed_session_candidates(viewer_id);
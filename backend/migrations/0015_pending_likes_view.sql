@@ -0,0 +1,26 @@
+-- LIKEs still waiting for an answer, with the inbox exclusions applied once for every reader
+-- (the inbox list and the badge count). Leaves out likes the recipient answered, matched or
+-- unmatched pairs, blocks in either direction, and senders whose profile is gone or incomplete
+CREATE OR REPLACE VIEW pending_likes AS
+SELECT i.*
+FROM interactions i
+INNER JOIN users u ON u.id = i.from_user_id AND u.is_profile_complete
+INNER JOIN profiles p ON p.user_id = i.from_user_id
+WHERE i.action = 'LIKE'
+  AND NOT EXISTS (
+    SELECT 1 FROM interactions back
+    WHERE back.from_user_id = i.to_user_id AND back.to_user_id = i.from_user_id
+  )
+  AND NOT EXISTS (
+    SELECT 1 FROM matches m
+    WHERE m.user1_id = LEAST(i.from_user_id, i.to_user_id) AND m.user2_id = GREATEST(i.from_user_id, i.to_user_id)
+  )
+  AND NOT EXISTS (
+    SELECT 1 FROM unmatches um
+    WHERE um.user1_id = LEAST(i.from_user_id, i.to_user_id) AND um.user2_id = GREATEST(i.from_user_id, i.to_user_id)
+  )
+  AND NOT EXISTS (
+    SELECT 1 FROM blocks b
+    WHERE (b.blocker_id = i.to_user_id AND b.blocked_id = i.from_user_id)
+       OR (b.blocker_id = i.from_user_id AND b.blocked_id = i.to_user_id)
+  );
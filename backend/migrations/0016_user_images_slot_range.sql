@@ -0,0 +1,32 @@
+-- Profile photos live in slots 0 to 5 (images_queries::IMAGE_SLOTS). Rows past the last slot
+-- are left over from before uploads picked a slot (0011 compacted each status separately, so a
+-- user with more than 6 images kept the extras). They are archived rather than deleted: the
+-- rows still point at stored objects that have to be cleaned up
+CREATE TABLE IF NOT EXISTS user_images_archive (
+    LIKE user_images,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+WITH overflow AS (
+    DELETE FROM user_images
+    WHERE display_order NOT BETWEEN 0 AND 5
+    RETURNING *
+)
+INSERT INTO user_images_archive
+SELECT overflow.*, NOW() FROM overflow;
+
+-- Archived ready images no longer count
+UPDATE profiles p
+SET image_count = (
+    SELECT COUNT(*) FROM user_images ui
+    WHERE ui.user_id = p.user_id AND ui.upload_status = 'ready'
+)
+WHERE p.user_id IN (SELECT user_id FROM user_images_archive);
+
+ALTER TABLE user_images
+    ADD CONSTRAINT user_images_slot_range CHECK (display_order BETWEEN 0 AND 5);
+
+-- upload_status is part of the key on purpose: a slot holds the shown image and, while a
+-- replacement is uploading, the pending one. Confirming the pending image deletes the ready one
+COMMENT ON CONSTRAINT user_images_slot_key ON user_images IS
+    'One ready and one pending image per slot: a pending upload replaces the ready image in its slot once confirmed';
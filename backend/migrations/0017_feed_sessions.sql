@@ -0,0 +1,18 @@
+-- A feed session: what its pages need besides the ranked candidates. The cursor only
+-- carries the session id and a position, so a client can't page with another seed
+CREATE TABLE IF NOT EXISTS feed_sessions (
+    id UUID PRIMARY KEY,
+    viewer_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    seed TEXT NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_feed_sessions_viewer ON feed_sessions(viewer_id);
+
+-- Sessions stored so far have no seed to go with them: their cursors get the
+-- "session expired" answer and the client starts a new one
+DELETE FROM feed_session_candidates;
+
+ALTER TABLE feed_session_candidates
+    ADD CONSTRAINT feed_session_candidates_session_id_fkey
+        FOREIGN KEY (session_id) REFERENCES feed_sessions(id) ON DELETE CASCADE;
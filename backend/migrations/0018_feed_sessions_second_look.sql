@@ -0,0 +1,2 @@
+-- Second look mode is chosen when a session starts; its pages keep the same eligibility
+ALTER TABLE feed_sessions ADD COLUMN IF NOT EXISTS second_look_days INT;
@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+/// A user's walk through the feed. Every candidate is ranked once when the session
+/// starts and stored with it; pages follow that stored order
+#[derive(Debug, FromRow)]
+pub struct FeedSession {
+    pub id: Uuid,
+    /// Shuffle of the session (see crate::ranking), rotates daily
+    pub seed: String,
+    pub started_at: DateTime<Utc>,
+    /// Second look mode of the session (see FeedConfig::second_look_days)
+    pub second_look_days: Option<i32>,
+}
+
+/// Store a new feed session and its ranked candidates as (candidate id, score, sort_hash).
+/// It replaces the viewer's previous session, whose cursors stop working
+pub async fn start_session(
+    pool: &PgPool,
+    viewer_id: &Uuid,
+    session: &FeedSession,
+    ranked: &[(&str, f64, &str)],
+) -> Result<(), sqlx::Error> {
+    let candidate_ids: Vec<&str> = ranked.iter().map(|(id, _, _)| *id).collect();
+    let scores: Vec<f64> = ranked.iter().map(|(_, score, _)| *score).collect();
+    let sort_hashes: Vec<&str> = ranked.iter().map(|(_, _, sort_hash)| *sort_hash).collect();
+
+    let mut tx = pool.begin().await?;
+
+    // The candidates go with it
+    sqlx::query("DELETE FROM feed_sessions WHERE viewer_id = $1")
+        .bind(viewer_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"INSERT INTO feed_sessions (id, viewer_id, seed, started_at, second_look_days)
+           VALUES ($1, $2, $3, $4, $5)"#
+    )
+    .bind(session.id)
+    .bind(viewer_id)
+    .bind(&session.seed)
+    .bind(session.started_at)
+    .bind(session.second_look_days)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"INSERT INTO feed_session_candidates (session_id, viewer_id, candidate_id, score, sort_hash)
+           SELECT $1, $2, c.candidate_id::UUID, c.score, c.sort_hash
+           FROM UNNEST($3::TEXT[], $4::FLOAT8[], $5::TEXT[]) AS c(candidate_id, score, sort_hash)"#
+    )
+    .bind(session.id)
+    .bind(viewer_id)
+    .bind(&candidate_ids)
+    .bind(&scores)
+    .bind(&sort_hashes)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// The viewer's session, None once a newer session replaced it
+pub async fn get_session(pool: &PgPool, viewer_id: &Uuid, session_id: &Uuid) -> Result<Option<FeedSession>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, seed, started_at, second_look_days FROM feed_sessions WHERE id = $1 AND viewer_id = $2"
+    )
+    .bind(session_id)
+    .bind(viewer_id)
+    .fetch_optional(pool)
+    .await
+}
@@ -1,11 +1,32 @@
 #![allow(unused)]
 
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
-/// Count the number of images for a user
+/// Profile photos go in slots 0 to 5 (display_order)
+pub const IMAGE_SLOTS: i32 = 6;
+
+/// An upload `mark_image_ready` confirmed
+pub struct ConfirmedImage {
+    /// Its slot, a reorder may have moved it since the upload started
+    pub slot: i32,
+    /// Key of the image it replaced in its slot, if any (its object should be deleted)
+    pub replaced_key: Option<String>,
+}
+
+/// Serialize the changes to one user's images, so two requests can't race for a slot
+async fn lock_user_images(tx: &mut Transaction<'_, Postgres>, user_id: &Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('user_images:' || $1::TEXT))")
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+/// Count the ready (confirmed) images of a user
 pub async fn count_images(pool: &PgPool, user_id: &Uuid) -> Result<i64, sqlx::Error> {
-    let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM user_images WHERE user_id = $1")
+    let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM user_images WHERE user_id = $1 AND upload_status = 'ready'")
         .bind(user_id)
         .fetch_one(pool)
         .await?;
@@ -13,43 +34,266 @@ pub async fn count_images(pool: &PgPool, user_id: &Uuid) -> Result<i64, sqlx::Er
     Ok(row.0)
 }
 
-/// Upload a profile image (max 6 allowed)
-pub async fn upload_profile_images(pool: &PgPool, user_id: &Uuid, image_url: &str) -> Result<(), sqlx::Error> {
-    let count = count_images(pool, user_id).await?;
+/// Store the number of ready images in profiles.image_count
+async fn sync_image_count(tx: &mut Transaction<'_, Postgres>, user_id: &Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"UPDATE profiles SET image_count = (
+               SELECT COUNT(*) FROM user_images WHERE user_id = $1 AND upload_status = 'ready'
+           )
+           WHERE user_id = $1"#
+    )
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
 
-    if count >= 6 {
-        return Err(sqlx::Error::Protocol("Maximum 6 images allowed".to_string()));
-    }
+    Ok(())
+}
 
-    let display_order = count as i32;
+/// Add a pending profile image in `slot` (0 to 5), returns its id and the key of the
+/// pending upload it replaced, if any (its object should be deleted).
+/// The image in the slot stays shown until `mark_image_ready` confirms the new one
+pub async fn upload_profile_images(
+    pool: &PgPool,
+    user_id: &Uuid,
+    slot: i32,
+    image_url: &str,
+) -> Result<(Uuid, Option<String>), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    lock_user_images(&mut tx, user_id).await?;
 
-    sqlx::query(
-        "INSERT INTO user_images (user_id, url, display_order) VALUES ($1, $2, $3)"
+    // One upload in progress per slot, the latest request wins
+    let replaced: Option<(String,)> = sqlx::query_as(
+        "DELETE FROM user_images WHERE user_id = $1 AND display_order = $2 AND upload_status = 'pending' RETURNING url"
+    )
+    .bind(user_id)
+    .bind(slot)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let row: (Uuid,) = sqlx::query_as(
+        "INSERT INTO user_images (user_id, url, display_order) VALUES ($1, $2, $3) RETURNING id"
     )
     .bind(user_id)
     .bind(image_url)
-    .bind(display_order)
-    .execute(pool)
+    .bind(slot)
+    .fetch_one(&mut *tx)
     .await?;
 
-    Ok(())
+    tx.commit().await?;
+
+    Ok((row.0, replaced.map(|(key,)| key)))
 }
 
-/// Delete a profile image by display order
-pub async fn delete_profile_images(pool: &PgPool, user_id: &Uuid, display_order: i32) -> Result<(), sqlx::Error> {
-    sqlx::query("DELETE FROM user_images WHERE user_id = $1 AND display_order = $2")
+/// Get the (key, display_order, upload_status) of one of the user's images
+pub async fn get_image_upload(
+    pool: &PgPool,
+    user_id: &Uuid,
+    image_id: &Uuid,
+) -> Result<Option<(String, i32, String)>, sqlx::Error> {
+    sqlx::query_as("SELECT url, display_order, upload_status FROM user_images WHERE id = $1 AND user_id = $2")
+        .bind(image_id)
         .bind(user_id)
-        .bind(display_order)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Mark one of the user's uploads as confirmed, the image is shown from now on in place
+/// of the one in its slot. Returns None, changing nothing, unless the image is still pending
+/// (a concurrent request confirmed or deleted it first, or the reaper got to it)
+pub async fn mark_image_ready(
+    pool: &PgPool,
+    user_id: &Uuid,
+    image_id: &Uuid,
+) -> Result<Option<ConfirmedImage>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    lock_user_images(&mut tx, user_id).await?;
+
+    // The image it replaces is still ready in the slot until the delete below
+    sqlx::query("SET CONSTRAINTS user_images_slot_key DEFERRED")
+        .execute(&mut *tx)
+        .await?;
+
+    let confirmed: Option<(i32,)> = sqlx::query_as(
+        r#"UPDATE user_images SET upload_status = 'ready'
+           WHERE id = $1 AND user_id = $2 AND upload_status = 'pending'
+           RETURNING display_order"#
+    )
+    .bind(image_id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some((slot,)) = confirmed else {
+        tx.rollback().await?;
+        return Ok(None);
+    };
+
+    let replaced: Option<(String,)> = sqlx::query_as(
+        r#"DELETE FROM user_images
+           WHERE user_id = $1 AND display_order = $2 AND upload_status = 'ready' AND id <> $3
+           RETURNING url"#
+    )
+    .bind(user_id)
+    .bind(slot)
+    .bind(image_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    sync_image_count(&mut tx, user_id).await?;
+
+    tx.commit().await?;
+
+    Ok(Some(ConfirmedImage {
+        slot,
+        replaced_key: replaced.map(|(key,)| key),
+    }))
+}
+
+/// Move the user's ready images to the slots given by their position in `image_ids`.
+/// A pending upload moves with the ready image it is going to replace; one into an empty
+/// slot (a new photo) goes after the reordered images, keeping the slot order of such uploads.
+/// Returns false, changing nothing, unless `image_ids` are exactly the user's ready images
+pub async fn reorder_images(pool: &PgPool, user_id: &Uuid, image_ids: &[Uuid]) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    lock_user_images(&mut tx, user_id).await?;
+
+    // Images swap slots, so uniqueness is only checked at commit
+    sqlx::query("SET CONSTRAINTS user_images_slot_key DEFERRED")
+        .execute(&mut *tx)
+        .await?;
+
+    let (ready,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM user_images WHERE user_id = $1 AND upload_status = 'ready'"
+    )
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // At most 6 ready images and 6 slots, so the new photos still fit after them
+    let moved: Vec<(String,)> = sqlx::query_as(
+        r#"WITH ready AS (
+               SELECT ui.display_order AS old_slot, o.position - 1 AS new_slot
+               FROM UNNEST($2::UUID[]) WITH ORDINALITY AS o(id, position)
+               INNER JOIN user_images ui
+                   ON ui.id = o.id AND ui.user_id = $1 AND ui.upload_status = 'ready'
+           ),
+           additions AS (
+               SELECT ui.display_order AS old_slot,
+                      (SELECT COUNT(*) FROM ready) + ROW_NUMBER() OVER (ORDER BY ui.display_order) - 1 AS new_slot
+               FROM user_images ui
+               WHERE ui.user_id = $1 AND ui.upload_status = 'pending'
+                 AND ui.display_order NOT IN (SELECT old_slot FROM ready)
+           )
+           UPDATE user_images ui
+           SET display_order = moves.new_slot
+           FROM (SELECT * FROM ready UNION ALL SELECT * FROM additions) moves
+           WHERE ui.user_id = $1 AND ui.display_order = moves.old_slot
+           RETURNING ui.upload_status"#
+    )
+    .bind(user_id)
+    .bind(image_ids)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let moved_ready = moved.iter().filter(|(status,)| status == "ready").count();
+    if moved_ready != image_ids.len() || ready != image_ids.len() as i64 {
+        tx.rollback().await?;
+        return Ok(false);
+    }
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// Pending uploads older than `ttl_seconds`, as (id, key)
+pub async fn get_stale_pending_images(pool: &PgPool, ttl_seconds: i64) -> Result<Vec<(Uuid, String)>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT id, url FROM user_images
+           WHERE upload_status = 'pending'
+             AND created_at < NOW() - make_interval(secs => $1::FLOAT8)"#
+    )
+    .bind(ttl_seconds)
+    .fetch_all(pool)
+    .await
+}
+
+/// Delete an image row if it is still pending, returns whether it did
+pub async fn delete_pending_image(pool: &PgPool, image_id: &Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM user_images WHERE id = $1 AND upload_status = 'pending'")
+        .bind(image_id)
         .execute(pool)
         .await?;
 
-    Ok(())
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete an image row inside `tx` if it is still pending, returns whether it did.
+/// The row stays locked until `tx` ends, so a confirm of the same image waits for the
+/// outcome: it finds nothing to confirm if `tx` commits, the pending image if it rolls back
+pub async fn claim_pending_image(tx: &mut Transaction<'_, Postgres>, image_id: &Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM user_images WHERE id = $1 AND upload_status = 'pending'")
+        .bind(image_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete one of the user's images, returns the keys of the objects to delete
+/// (empty if there was no such image). Deleting a ready image drops the upload meant
+/// to replace it too, and the later images move up a slot to close the gap.
+/// Callers should follow up with `profile_queries::refresh_profile_complete`
+pub async fn delete_profile_images(pool: &PgPool, user_id: &Uuid, image_id: &Uuid) -> Result<Vec<String>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    lock_user_images(&mut tx, user_id).await?;
+
+    let deleted: Option<(String, i32, String)> = sqlx::query_as(
+        "DELETE FROM user_images WHERE id = $1 AND user_id = $2 RETURNING url, display_order, upload_status"
+    )
+    .bind(image_id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some((key, slot, status)) = deleted else {
+        return Ok(Vec::new());
+    };
+    let mut keys = vec![key];
+
+    if status == "ready" {
+        let replacement: Option<(String,)> = sqlx::query_as(
+            "DELETE FROM user_images WHERE user_id = $1 AND display_order = $2 RETURNING url"
+        )
+        .bind(user_id)
+        .bind(slot)
+        .fetch_optional(&mut *tx)
+        .await?;
+        keys.extend(replacement.map(|(key,)| key));
+
+        // Rows shift one by one, uniqueness is only checked at commit
+        sqlx::query("SET CONSTRAINTS user_images_slot_key DEFERRED")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE user_images SET display_order = display_order - 1 WHERE user_id = $1 AND display_order > $2")
+            .bind(user_id)
+            .bind(slot)
+            .execute(&mut *tx)
+            .await?;
+
+        sync_image_count(&mut tx, user_id).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(keys)
 }
 
-/// Get all images for a user
+/// Get the ready images of a user
 pub async fn get_user_images(pool: &PgPool, user_id: &Uuid) -> Result<Vec<(Uuid, String, i32)>, sqlx::Error> {
     let rows: Vec<(Uuid, String, i32)> = sqlx::query_as(
-        "SELECT id, url, display_order FROM user_images WHERE user_id = $1 ORDER BY display_order"
+        "SELECT id, url, display_order FROM user_images WHERE user_id = $1 AND upload_status = 'ready' ORDER BY display_order"
     )
     .bind(user_id)
     .fetch_all(pool)
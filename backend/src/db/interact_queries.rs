@@ -1,53 +1,195 @@
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
 use uuid::Uuid;
 
-use crate::models::inputs::InteractRequest;
+use crate::models::inputs::{InteractAction, InteractContextType, InteractRequest};
 use crate::models::outputs::Interaction;
 
+/// Record an interaction (LIKE / PASS) from one user to another.
+/// If this is a LIKE and the target already liked the sender, a match is
+/// created in the same transaction.
+/// Returns Some(match_id) when the interaction resulted in a match
 pub async fn interact(
     pool: &PgPool,
     from_user_id: &Uuid,  
     to_user_id: &Uuid,
     body: &InteractRequest,
-) -> Result<(), sqlx::Error> {
+) -> Result<Option<Uuid>, sqlx::Error> {
     // Extract context_type and context_id from the optional context
     let (context_type, context_id) = match &body.context {
-        Some(ctx) => (Some(ctx.r#type.clone()), Some(ctx.id.clone())),
+        Some(ctx) => (Some(ctx.r#type.as_str()), Some(ctx.id.as_str())),
         None => (None, None),
     };
 
+    let mut tx = pool.begin().await?;
+
+    // Serialize concurrent interactions between the same pair of users so two
+    // simultaneous LIKEs can't both miss each other and skip the match
+    sqlx::query(
+        "SELECT pg_advisory_xact_lock(hashtext(LEAST($1, $2)::TEXT || GREATEST($1, $2)::TEXT))"
+    )
+    .bind(from_user_id)
+    .bind(to_user_id)
+    .execute(&mut *tx)
+    .await?;
+
     sqlx::query(
         r#"INSERT INTO interactions (from_user_id, to_user_id, action, context_type, context_id, comment) 
            VALUES ($1, $2, $3, $4, $5, $6)
            ON CONFLICT (from_user_id, to_user_id) 
-           DO UPDATE SET action = $3, context_type = $4, context_id = $5, comment = $6"#
+           DO UPDATE SET action = $3, context_type = $4, context_id = $5, comment = $6, created_at = NOW()"#
     )
     .bind(from_user_id)
     .bind(to_user_id)
-    .bind(&body.action)
-    .bind(&context_type)
-    .bind(&context_id)
+    .bind(body.action.as_str())
+    .bind(context_type)
+    .bind(context_id)
     .bind(&body.comment)
-    .execute(pool)
+    .execute(&mut *tx)
+    .await?;
+
+    if body.action != InteractAction::Like {
+        tx.commit().await?;
+        return Ok(None);
+    }
+
+    // Did the target already like the sender?
+    // Pairs that unmatched or blocked each other never match (again)
+    let reciprocal: Option<(Uuid,)> = sqlx::query_as(
+        r#"SELECT id FROM interactions
+           WHERE from_user_id = $1 AND to_user_id = $2 AND action = 'LIKE'
+             AND NOT EXISTS (
+               SELECT 1 FROM unmatches um
+               WHERE um.user1_id = LEAST($1, $2) AND um.user2_id = GREATEST($1, $2)
+             )
+             AND NOT EXISTS (
+               SELECT 1 FROM blocks b
+               WHERE (b.blocker_id = $1 AND b.blocked_id = $2)
+                  OR (b.blocker_id = $2 AND b.blocked_id = $1)
+             )"#
+    )
+    .bind(to_user_id)
+    .bind(from_user_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if reciprocal.is_none() {
+        tx.commit().await?;
+        return Ok(None);
+    }
+
+    // Matches are stored with the smaller user id first so each pair has exactly one row
+    let row: (Uuid,) = sqlx::query_as(
+        r#"INSERT INTO matches (user1_id, user2_id)
+           VALUES (LEAST($1, $2), GREATEST($1, $2))
+           ON CONFLICT (user1_id, user2_id)
+           DO UPDATE SET user1_id = EXCLUDED.user1_id
+           RETURNING id"#
+    )
+    .bind(from_user_id)
+    .bind(to_user_id)
+    .fetch_one(&mut *tx)
     .await?;
 
-    Ok(())
+    tx.commit().await?;
+
+    Ok(Some(row.0))
 }
 
-pub async fn get_interactions_to_user_id(
+/// Check that the liked image / prompt belongs to the target user
+pub async fn context_belongs_to(
+    pool: &PgPool,
+    context_type: InteractContextType,
+    context_id: &Uuid,
+    owner_id: &Uuid,
+) -> Result<bool, sqlx::Error> {
+    let query = match context_type {
+        InteractContextType::Image => {
+            "SELECT id FROM user_images WHERE id = $1 AND user_id = $2 AND upload_status = 'ready'"
+        }
+        InteractContextType::Prompt => "SELECT id FROM user_prompts WHERE id = $1 AND user_id = $2",
+    };
+
+    let row: Option<(Uuid,)> = sqlx::query_as(query)
+        .bind(context_id)
+        .bind(owner_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.is_some())
+}
+
+/// One like of the inbox, with the liker's card and the liked content
+#[derive(Debug, FromRow)]
+pub struct LikeRow {
+    pub id: Uuid,
+    pub from_user_id: Uuid,
+    pub name: Option<String>,
+    pub birthdate: Option<String>,
+    pub photo_key: Option<String>,
+    pub image_id: Option<Uuid>,
+    pub image_key: Option<String>,
+    pub prompt_id: Option<Uuid>,
+    pub prompt_question: Option<String>,
+    pub prompt_answer: Option<String>,
+    pub comment: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The LIKEs a user received and hasn't answered yet, most recent first.
+/// The exclusions (matched / unmatched pairs, blocked users, deleted or incomplete
+/// profiles) live in the `pending_likes` view, shared with `count_pending_likes`.
+/// `cursor` is the id of the last like of the previous page
+pub async fn get_likes_inbox(
     pool: &PgPool,
     user_id: &Uuid,
-    action: &str    
-) -> Result<Vec<Interaction>, sqlx::Error> {
-    let interactions = sqlx::query_as(
-        r#"SELECT * FROM interactions WHERE to_user_id = $1 AND action = $2 ORDER BY created_at DESC"#
+    cursor: Option<Uuid>,
+    limit: i64,
+) -> Result<Vec<LikeRow>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, LikeRow>(
+        r#"
+        SELECT
+            i.id,
+            i.from_user_id,
+            p.name,
+            p.birthdate::TEXT AS birthdate,
+            photo.url AS photo_key,
+            li.id AS image_id,
+            li.url AS image_key,
+            lp.id AS prompt_id,
+            lp.question AS prompt_question,
+            lp.answer AS prompt_answer,
+            i.comment,
+            i.created_at
+        FROM pending_likes i
+        INNER JOIN profiles p ON p.user_id = i.from_user_id
+        LEFT JOIN LATERAL (
+            SELECT ui.url FROM user_images ui
+            WHERE ui.user_id = i.from_user_id AND ui.upload_status = 'ready'
+            ORDER BY ui.display_order
+            LIMIT 1
+        ) photo ON TRUE
+        -- the liked content is the caller's; gone if they deleted it since
+        LEFT JOIN user_images li
+            ON i.context_type = 'IMAGE' AND li.id::TEXT = i.context_id AND li.user_id = $1
+        LEFT JOIN user_prompts lp
+            ON i.context_type = 'PROMPT' AND lp.id::TEXT = i.context_id AND lp.user_id = $1
+        WHERE i.to_user_id = $1
+          AND (
+            $2::UUID IS NULL
+            OR (i.created_at, i.id) < (SELECT c.created_at, c.id FROM interactions c WHERE c.id = $2)
+          )
+        ORDER BY i.created_at DESC, i.id DESC
+        LIMIT $3
+        "#,
     )
     .bind(user_id)
-    .bind(action)
+    .bind(cursor)
+    .bind(limit)
     .fetch_all(pool)
     .await?;
 
-    Ok(interactions)
+    Ok(rows)
 }
 
 pub async fn get_interactions_from_user_id(
@@ -56,7 +198,14 @@ pub async fn get_interactions_from_user_id(
     action: &str,
 ) -> Result<Vec<Interaction>, sqlx::Error> {
     let interactions = sqlx::query_as(
-        r#"SELECT * FROM interactions WHERE from_user_id = $1 AND action = $2 ORDER BY created_at DESC"#
+        r#"SELECT i.* FROM interactions i
+           WHERE i.from_user_id = $1 AND i.action = $2
+             AND NOT EXISTS (
+               SELECT 1 FROM blocks b
+               WHERE (b.blocker_id = $1 AND b.blocked_id = i.to_user_id)
+                  OR (b.blocker_id = i.to_user_id AND b.blocked_id = $1)
+             )
+           ORDER BY i.created_at DESC"#
     )
     .bind(user_id)
     .bind(action)
@@ -64,4 +213,18 @@ pub async fn get_interactions_from_user_id(
     .await?;
 
     Ok(interactions)
-}
\ No newline at end of file
+}
+
+/// Count the LIKEs a user received and hasn't answered yet, the ones `get_likes_inbox` lists
+pub async fn count_pending_likes(pool: &PgPool, user_id: &Uuid) -> Result<i64, sqlx::Error> {
+    let row: (i64,) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*) FROM pending_likes WHERE to_user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.0)
+}
@@ -0,0 +1,328 @@
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+/// One row of the matches list, seen from the perspective of the caller
+#[derive(Debug, FromRow)]
+pub struct MatchListRow {
+    pub match_id: Uuid,
+    pub other_user_id: Uuid,
+    pub other_is_profile_complete: bool,
+    pub other_is_new_user: bool,
+    pub other_name: Option<String>,
+    pub other_photo_key: Option<String>,
+    pub last_message: Option<String>,
+    pub last_message_at: Option<DateTime<Utc>>,
+    pub last_message_is_read: Option<bool>,
+    pub unread_count: i64,
+}
+
+/// Get the matches of a user, most recent activity first.
+/// Activity is the last message time, or the match time if nothing was sent yet.
+/// `cursor` is the id of the last match of the previous page
+pub async fn get_matches_for_user(
+    pool: &PgPool,
+    user_id: &Uuid,
+    cursor: Option<Uuid>,
+    limit: i64,
+) -> Result<Vec<MatchListRow>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, MatchListRow>(
+        r#"
+        SELECT
+            m.id AS match_id,
+            other.id AS other_user_id,
+            COALESCE(other.is_profile_complete, FALSE) AS other_is_profile_complete,
+            COALESCE(other.is_new_user, FALSE) AS other_is_new_user,
+            p.name AS other_name,
+            photo.url AS other_photo_key,
+            m.last_message,
+            m.last_message_at,
+            latest.is_read AS last_message_is_read,
+            (
+                SELECT COUNT(*) FROM messages msg
+                WHERE msg.match_id = m.id
+                  AND msg.sender_id <> $1
+                  AND msg.is_read = FALSE
+            ) AS unread_count
+        FROM matches m
+        INNER JOIN users other
+            ON other.id = CASE WHEN m.user1_id = $1 THEN m.user2_id ELSE m.user1_id END
+        LEFT JOIN profiles p ON p.user_id = other.id
+        LEFT JOIN LATERAL (
+            SELECT ui.url FROM user_images ui
+            WHERE ui.user_id = other.id AND ui.upload_status = 'ready'
+            ORDER BY ui.display_order
+            LIMIT 1
+        ) photo ON TRUE
+        LEFT JOIN LATERAL (
+            SELECT msg.is_read FROM messages msg
+            WHERE msg.match_id = m.id
+            ORDER BY msg.created_at DESC
+            LIMIT 1
+        ) latest ON TRUE
+        WHERE (m.user1_id = $1 OR m.user2_id = $1)
+          AND NOT EXISTS (
+            SELECT 1 FROM blocks b
+            WHERE (b.blocker_id = $1 AND b.blocked_id = other.id)
+               OR (b.blocker_id = other.id AND b.blocked_id = $1)
+          )
+          AND (
+            $2::UUID IS NULL
+            OR (COALESCE(m.last_message_at, m.created_at), m.id) < (
+                SELECT COALESCE(c.last_message_at, c.created_at), c.id
+                FROM matches c WHERE c.id = $2
+            )
+          )
+        ORDER BY COALESCE(m.last_message_at, m.created_at) DESC, m.id DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(cursor)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// A chat message row
+#[derive(Debug, FromRow)]
+pub struct MessageRow {
+    pub id: Uuid,
+    pub match_id: Uuid,
+    pub sender_id: Uuid,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+    pub is_read: bool,
+}
+
+/// Check if a user is one of the two participants of a match.
+/// A match between users who blocked each other counts as hidden
+pub async fn is_match_participant(
+    pool: &PgPool,
+    match_id: &Uuid,
+    user_id: &Uuid,
+) -> Result<bool, sqlx::Error> {
+    let row: Option<(Uuid,)> = sqlx::query_as(
+        r#"SELECT m.id FROM matches m
+           WHERE m.id = $1 AND (m.user1_id = $2 OR m.user2_id = $2)
+             AND NOT EXISTS (
+               SELECT 1 FROM blocks b
+               WHERE (b.blocker_id = m.user1_id AND b.blocked_id = m.user2_id)
+                  OR (b.blocker_id = m.user2_id AND b.blocked_id = m.user1_id)
+             )"#
+    )
+    .bind(match_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.is_some())
+}
+
+/// Get the two participants of a match as (user1_id, user2_id).
+/// Matches hidden by a block between the two are treated as missing
+pub async fn get_match_participants(
+    pool: &PgPool,
+    match_id: &Uuid,
+) -> Result<Option<(Uuid, Uuid)>, sqlx::Error> {
+    let row: Option<(Uuid, Uuid)> = sqlx::query_as(
+        r#"SELECT m.user1_id, m.user2_id FROM matches m
+           WHERE m.id = $1
+             AND NOT EXISTS (
+               SELECT 1 FROM blocks b
+               WHERE (b.blocker_id = m.user1_id AND b.blocked_id = m.user2_id)
+                  OR (b.blocker_id = m.user2_id AND b.blocked_id = m.user1_id)
+             )"#
+    )
+    .bind(match_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+/// Unmatch: record the pair in `unmatches` and delete the match (its messages cascade)
+pub async fn unmatch(
+    pool: &PgPool,
+    match_id: &Uuid,
+    user_id: &Uuid,
+    other_user_id: &Uuid,
+    reason: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"INSERT INTO unmatches (user1_id, user2_id, unmatched_by, reason)
+           VALUES (LEAST($1, $2), GREATEST($1, $2), $1, $3)
+           ON CONFLICT (user1_id, user2_id)
+           DO UPDATE SET unmatched_by = $1, reason = $3, created_at = NOW()"#
+    )
+    .bind(user_id)
+    .bind(other_user_id)
+    .bind(reason)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM matches WHERE id = $1")
+        .bind(match_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Insert a message and update the match's last message preview in one transaction
+pub async fn insert_message(
+    pool: &PgPool,
+    match_id: &Uuid,
+    sender_id: &Uuid,
+    text: &str,
+) -> Result<MessageRow, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let message = sqlx::query_as::<_, MessageRow>(
+        r#"INSERT INTO messages (match_id, sender_id, text)
+           VALUES ($1, $2, $3)
+           RETURNING id, match_id, sender_id, text, created_at, is_read"#
+    )
+    .bind(match_id)
+    .bind(sender_id)
+    .bind(text)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE matches SET last_message = $2, last_message_at = $3 WHERE id = $1")
+        .bind(match_id)
+        .bind(&message.text)
+        .bind(message.created_at)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(message)
+}
+
+/// Get a page of messages of a match in chronological order.
+/// `before` / `after` are message ids; without either, the latest page is returned
+pub async fn get_messages(
+    pool: &PgPool,
+    match_id: &Uuid,
+    before: Option<Uuid>,
+    after: Option<Uuid>,
+    limit: i64,
+) -> Result<Vec<MessageRow>, sqlx::Error> {
+    if let Some(after_id) = after {
+        // Newer than the cursor, oldest first
+        let rows = sqlx::query_as::<_, MessageRow>(
+            r#"
+            SELECT id, match_id, sender_id, text, created_at, is_read
+            FROM messages
+            WHERE match_id = $1
+              AND (created_at, id) > (SELECT created_at, id FROM messages WHERE id = $2 AND match_id = $1)
+            ORDER BY created_at ASC, id ASC
+            LIMIT $3
+            "#,
+        )
+        .bind(match_id)
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        return Ok(rows);
+    }
+
+    // Older than the cursor (or the latest page), fetched newest first then flipped
+    let mut rows = sqlx::query_as::<_, MessageRow>(
+        r#"
+        SELECT id, match_id, sender_id, text, created_at, is_read
+        FROM messages
+        WHERE match_id = $1
+          AND (
+            $2::UUID IS NULL
+            OR (created_at, id) < (SELECT created_at, id FROM messages WHERE id = $2 AND match_id = $1)
+          )
+        ORDER BY created_at DESC, id DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(match_id)
+    .bind(before)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    rows.reverse();
+
+    Ok(rows)
+}
+
+/// Mark every message the other participant sent in a match, up to and
+/// including `up_to_message_id`, as read by `reader_id`.
+/// Returns None if the message doesn't belong to the match, otherwise the number of messages marked
+pub async fn mark_messages_read(
+    pool: &PgPool,
+    match_id: &Uuid,
+    reader_id: &Uuid,
+    up_to_message_id: &Uuid,
+) -> Result<Option<u64>, sqlx::Error> {
+    let target: Option<(DateTime<Utc>,)> = sqlx::query_as(
+        "SELECT created_at FROM messages WHERE id = $1 AND match_id = $2"
+    )
+    .bind(up_to_message_id)
+    .bind(match_id)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some((created_at,)) = target else {
+        return Ok(None);
+    };
+
+    let result = sqlx::query(
+        r#"
+        UPDATE messages SET is_read = TRUE
+        WHERE match_id = $1
+          AND sender_id <> $2
+          AND is_read = FALSE
+          AND (created_at, id) <= ($3, $4)
+        "#,
+    )
+    .bind(match_id)
+    .bind(reader_id)
+    .bind(created_at)
+    .bind(up_to_message_id)
+    .execute(pool)
+    .await?;
+
+    Ok(Some(result.rows_affected()))
+}
+
+/// Count unread messages sent to a user across all their matches.
+/// Returns (unread_messages, conversations_with_unread)
+pub async fn count_unread_for_user(pool: &PgPool, user_id: &Uuid) -> Result<(i64, i64), sqlx::Error> {
+    let row: (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*), COUNT(DISTINCT msg.match_id)
+        FROM messages msg
+        INNER JOIN matches m ON m.id = msg.match_id
+        WHERE (m.user1_id = $1 OR m.user2_id = $1)
+          AND msg.sender_id <> $1
+          AND msg.is_read = FALSE
+          AND NOT EXISTS (
+            SELECT 1 FROM blocks b
+            WHERE (b.blocker_id = $1 AND b.blocked_id = msg.sender_id)
+               OR (b.blocker_id = msg.sender_id AND b.blocked_id = $1)
+          )
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row)
+}
@@ -3,4 +3,7 @@ pub mod profile_queries;
 pub mod images_queries;
 pub mod prompt_queries;
 pub mod seed;
-pub mod interact_queries;
\ No newline at end of file
+pub mod interact_queries;
+pub mod match_queries;
+pub mod safety_queries;
+pub mod feed_queries;
\ No newline at end of file
@@ -1,8 +1,15 @@
-use crate::models::inputs::UpdateProfileRequest;
+use crate::db::{images_queries, prompt_queries};
+use crate::models::inputs::{GeoLocation, Preferences, UpdateProfileRequest};
 use crate::models::outputs::{ProfileDetails, SuggestionProfile};
+use crate::models::state::DistanceUnit;
 use sqlx::PgPool;
 use uuid::Uuid;
 
+/// Photos needed before a profile can be finalized (and shown in the feed)
+pub const REQUIRED_IMAGES: i64 = 6;
+/// Prompts needed before a profile can be finalized
+pub const REQUIRED_PROMPTS: i64 = 3;
+
 /// Check if a profile exists for a user
 pub async fn check_profile_exists(pool: &PgPool, user_id: &Uuid) -> Result<bool, sqlx::Error> {
     let row: Option<(Uuid,)> = sqlx::query_as("SELECT user_id FROM profiles WHERE user_id = $1")
@@ -23,8 +30,9 @@ pub async fn create_profile(
         r#"INSERT INTO profiles (
             user_id, name, bio, pronouns, gender, sexuality, height, 
             job, company, school, ethnicity, politics, religion,
-            relationship_type, dating_intention, drinks, smokes
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)"#,
+            relationship_type, dating_intention, drinks, smokes, birthdate, location
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18::DATE,
+            point($19::FLOAT8, $20::FLOAT8))"#,
     )
     .bind(user_id)
     .bind(&req.name)
@@ -32,7 +40,7 @@ pub async fn create_profile(
     .bind(&req.pronouns)
     .bind(&req.gender)
     .bind(&req.sexuality)
-    .bind(&req.height)
+    .bind(req.height)
     .bind(&req.job)
     .bind(&req.company)
     .bind(&req.school)
@@ -43,6 +51,9 @@ pub async fn create_profile(
     .bind(&req.dating_intention)
     .bind(&req.drinks)
     .bind(&req.smokes)
+    .bind(&req.birthdate)
+    .bind(req.location.map(|l| l.lng)) // POINT x
+    .bind(req.location.map(|l| l.lat)) // POINT y
     .execute(pool)
     .await?;
 
@@ -72,7 +83,9 @@ pub async fn update_profile(
             relationship_type = COALESCE($14, relationship_type),
             dating_intention = COALESCE($15, dating_intention),
             drinks = COALESCE($16, drinks),
-            smokes = COALESCE($17, smokes)
+            smokes = COALESCE($17, smokes),
+            birthdate = COALESCE($18::DATE, birthdate),
+            location = COALESCE(point($19::FLOAT8, $20::FLOAT8), location)
         WHERE user_id = $1"#,
     )
     .bind(user_id)
@@ -81,7 +94,7 @@ pub async fn update_profile(
     .bind(&req.pronouns)
     .bind(&req.gender)
     .bind(&req.sexuality)
-    .bind(&req.height)
+    .bind(req.height)
     .bind(&req.job)
     .bind(&req.company)
     .bind(&req.school)
@@ -92,6 +105,9 @@ pub async fn update_profile(
     .bind(&req.dating_intention)
     .bind(&req.drinks)
     .bind(&req.smokes)
+    .bind(&req.birthdate)
+    .bind(req.location.map(|l| l.lng)) // POINT x
+    .bind(req.location.map(|l| l.lat)) // POINT y
     .execute(pool)
     .await?;
 
@@ -115,33 +131,43 @@ pub async fn upsert_profile(
     }
 }
 
-/// Delete user account (profile + user)
-pub async fn delete_user(pool: &PgPool, user_id: &Uuid) -> Result<(), sqlx::Error> {
+/// Delete user account (profile + user), all or nothing.
+/// Returns the keys of the user's stored images, whose objects should be deleted
+pub async fn delete_user(pool: &PgPool, user_id: &Uuid) -> Result<Vec<String>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
     // Delete profile first (foreign key constraint)
     sqlx::query("DELETE FROM profiles WHERE user_id = $1")
         .bind(user_id)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
-    // Delete the user's images
-    sqlx::query("DELETE FROM user_images WHERE user_id = $1")
+    // Delete the user's images, pending uploads and archived ones included
+    let images: Vec<(String,)> = sqlx::query_as("DELETE FROM user_images WHERE user_id = $1 RETURNING url")
         .bind(user_id)
-        .execute(pool)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let archived: Vec<(String,)> = sqlx::query_as("DELETE FROM user_images_archive WHERE user_id = $1 RETURNING url")
+        .bind(user_id)
+        .fetch_all(&mut *tx)
         .await?;
 
     // Delete the user's prompts
     sqlx::query("DELETE FROM user_prompts WHERE user_id = $1")
         .bind(user_id)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
     // Delete user
     sqlx::query("DELETE FROM users WHERE id = $1")
         .bind(user_id)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
-    Ok(())
+    tx.commit().await?;
+
+    Ok(images.into_iter().chain(archived).map(|(key,)| key).collect())
 }
 
 /// Count how many profile attributes are still NULL (unfilled)
@@ -179,11 +205,53 @@ pub async fn check_profile_attributes_filled(
     Ok(row.0)
 }
 
+/// Whether the user has everything finalization asks for: images, prompts and all details
+pub async fn profile_requirements_met(pool: &PgPool, user_id: &Uuid) -> Result<bool, sqlx::Error> {
+    if images_queries::count_images(pool, user_id).await? < REQUIRED_IMAGES
+        || prompt_queries::count_prompts(pool, user_id).await? < REQUIRED_PROMPTS
+    {
+        return Ok(false);
+    }
+
+    match check_profile_attributes_filled(pool, user_id).await {
+        Ok(missing) => Ok(missing == 0),
+        Err(sqlx::Error::RowNotFound) => Ok(false), // no profile yet
+        Err(e) => Err(e),
+    }
+}
+
+/// Mark the profile as finalized, making it visible in the feed
+pub async fn set_profile_complete(pool: &PgPool, user_id: &Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE users SET is_profile_complete = TRUE WHERE id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Call after an edit: hide a finalized profile again if it no longer meets the requirements.
+/// Returns whether the flag was dropped
+pub async fn refresh_profile_complete(pool: &PgPool, user_id: &Uuid) -> Result<bool, sqlx::Error> {
+    if profile_requirements_met(pool, user_id).await? {
+        return Ok(false);
+    }
+
+    let result = sqlx::query(
+        "UPDATE users SET is_profile_complete = FALSE WHERE id = $1 AND is_profile_complete",
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
 pub async fn get_profile(pool: &PgPool, user_id: &Uuid) -> Result<ProfileDetails, sqlx::Error> {
     let row = sqlx::query_as::<_, ProfileDetails>(
         r#"
         SELECT name, bio, birthdate::TEXT, pronouns, gender, sexuality, height,
-            NULL as location, job, company, school, ethnicity, politics, religion,
+            location[1]::TEXT || ',' || location[0]::TEXT AS location, job, company, school, ethnicity, politics, religion,
             relationship_type, dating_intention, drinks, smokes
         FROM profiles WHERE user_id = $1
     "#,
@@ -195,120 +263,334 @@ pub async fn get_profile(pool: &PgPool, user_id: &Uuid) -> Result<ProfileDetails
     Ok(row)
 }
 
-/// Get profile suggestions based on user preferences
-/// For now: filters by gender_preference only, excludes current user
+/// Get the stored location of a user, if any
+pub async fn get_location(pool: &PgPool, user_id: &Uuid) -> Result<Option<GeoLocation>, sqlx::Error> {
+    let row: Option<(f64, f64)> = sqlx::query_as(
+        "SELECT location[1], location[0] FROM profiles WHERE user_id = $1 AND location IS NOT NULL",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|(lat, lng)| GeoLocation { lat, lng }))
+}
+
+/// Lat/lng box around `center` that contains every point within `distance`,
+/// as (min_lng, min_lat, max_lng, max_lat). None when it would wrap around a pole
+/// or the antimeridian, the haversine check alone is used then
+fn bounding_box(center: GeoLocation, distance: f64, earth_radius: f64) -> Option<(f64, f64, f64, f64)> {
+    let d_lat = (distance / earth_radius).to_degrees();
+    let min_lat = center.lat - d_lat;
+    let max_lat = center.lat + d_lat;
+    if min_lat <= -90.0 || max_lat >= 90.0 {
+        return None;
+    }
+
+    // Longitude degrees shrink towards the poles, use the widest latitude of the box
+    let widest = min_lat.abs().max(max_lat.abs()).to_radians();
+    let d_lng = d_lat / widest.cos();
+    let min_lng = center.lng - d_lng;
+    let max_lng = center.lng + d_lng;
+    if min_lng < -180.0 || max_lng > 180.0 {
+        return None;
+    }
+
+    Some((min_lng, min_lat, max_lng, max_lat))
+}
+
+/// SQL filter for a list preference: only dealbreakers filter, nice-to-haves are left
+/// to the ranker. Empty or missing lists are open to all
+fn dealbreaker_filter(values: Option<&Vec<String>>, dealbreaker: Option<bool>) -> Option<&[String]> {
+    values
+        .filter(|values| !values.is_empty() && dealbreaker.unwrap_or(true))
+        .map(|values| values.as_slice())
+}
+
+/// A page of a feed session stored with feed_queries::start_session
+pub struct SessionPage<'a> {
+    pub session_id: &'a Uuid,
+    /// Score and sort_hash of the last candidate already returned
+    pub after: Option<(f64, &'a str)>,
+    pub limit: i64,
+}
+
+/// What `get_suggestions` returns
+pub enum Suggestions<'a> {
+    /// The ranking inputs of a new feed session: up to this many candidates, the most
+    /// recently active first, without images or prompts
+    Pool(i64),
+    /// The next page of a stored session
+    Page(SessionPage<'a>),
+}
+
+/// Get profile suggestions based on user preferences, among finalized profiles only
+/// Filters by gender_preference (None = everyone), age_range and the dealbreaker
+/// ethnicity / religion preferences (nice-to-haves are left to the ranker).
+/// Matching is reciprocal: the user must also fit each candidate's own preferences
+/// (the normalized users.pref_* columns). A field the user hasn't filled in (gender,
+/// birthdate, ethnicity, religion, location) places no constraint, so an unfinished
+/// profile still gets a feed. Excludes:
+/// the current user, anyone they already liked / passed or matched with,
+/// unmatched pairs and blocked users.
+/// With `second_look_days`, profiles passed more than that many days ago come back.
+/// Profiles without a birthdate are left out whenever an age range is set.
+/// When the user has a location, `distance` is filled (rounded, in `distance_unit`)
+/// and distance_max applies; profiles without a location are left out in that case.
+/// `sort_hash` is the candidate's shuffle for `seed` (see crate::ranking), as stored
+/// in the session for a page.
+/// A page comes in the session's ranked order, images and prompts aggregated as JSON
+/// arrays in display order; candidates that stopped matching since the session started
+/// are skipped
 pub async fn get_suggestions(
     pool: &PgPool,
-    gender_preference: Option<Vec<String>>,
     user_id: &Uuid,
+    preferences: &Preferences,
+    second_look_days: Option<i32>,
+    distance_unit: DistanceUnit,
+    seed: &str,
+    wanted: &Suggestions<'_>,
 ) -> Result<Vec<SuggestionProfile>, sqlx::Error> {
-    println!(
-        "get_suggestions called with user_id: {:?}, gender_preference: {:?}",
-        user_id, gender_preference
-    );
-    // If gender_preference is provided, filter by it; otherwise return all profiles
-    let profiles = if let Some(genders) = gender_preference {
-        // Filter profiles where gender is in the preference list
-        sqlx::query_as::<_, SuggestionProfile>(
-            // SELECT user_id::TEXT as user_id, name, bio, birthdate::TEXT, pronouns, gender, sexuality, height,
-            //     NULL as location, job, company, school, ethnicity, politics, religion,
-            //     relationship_type, dating_intention, drinks, smokes
-            // FROM profiles
-            // WHERE gender = ANY($1) AND user_id != $2
-            // LIMIT 20
-            r#"
-            SELECT 
-                p.user_id::TEXT AS user_id,
-                p.name,
-                p.bio,
-                p.birthdate::TEXT,
-                p.pronouns,
-                p.gender,
-                p.sexuality,
-                p.height,
-                NULL AS location,
-                p.job,
-                p.company,
-                p.school,
-                p.ethnicity,
-                p.politics,
-                p.religion,
-                p.relationship_type,
-                p.dating_intention,
-                p.drinks,
-                p.smokes,
-
-                COALESCE(
-                    json_agg(
-                        json_build_object(
-                            'image_id', ui.id,
-                            'url', ui.url,
-                            'order', ui.display_order
-                        ) ORDER BY ui.display_order
-                    ) FILTER (WHERE ui.id IS NOT NULL), 
-                    '[]'
-                ) AS images
-
-            FROM profiles p
-            INNER JOIN users u ON p.user_id = u.id
-            LEFT JOIN user_images ui ON p.user_id = ui.user_id
-            WHERE p.gender = ANY($1) AND p.user_id != $2
-            GROUP BY 
-                p.user_id,
-                p.name,
-                p.bio,
-                p.birthdate,
-                p.pronouns,
-                p.gender,
-                p.sexuality,
-                p.height,
-                p.job,
-                p.company,
-                p.school,
-                p.ethnicity,
-                p.politics,
-                p.religion,
-                p.relationship_type,
-                p.dating_intention,
-                p.drinks,
-                p.smokes,
-                u.last_active
-
-                ORDER BY 
-                     CASE WHEN u.last_active > NOW() - INTERVAL '1 day' THEN 0
-                        WHEN u.last_active > NOW() - INTERVAL '1 week' THEN 1
-                        WHEN u.last_active > NOW() - INTERVAL '1 month' THEN 2
-                        ELSE 3
-                    END,
-                    MD5(p.user_id::TEXT || $3)
-                LIMIT 20 OFFSET $4
-                "#,
-        )
-        // ORDER BY RANDOM()
-        .bind(&genders)
-        .bind(user_id)
-        .bind("test-seed-123".to_string()) // default for testing
-        .bind(0) // default for testing
-        .fetch_all(pool)
-        .await?
-    } else {
-        // No preference - return all profiles except current user
-        sqlx::query_as::<_, SuggestionProfile>(
-            r#"
-            SELECT user_id::TEXT as user_id, name, bio, birthdate::TEXT, pronouns, gender, sexuality, height,
-                NULL as location, job, company, school, ethnicity, politics, religion,
-                relationship_type, dating_intention, drinks, smokes
-            FROM profiles 
-            WHERE user_id != $1
-            LIMIT 20
-        "#,
-        )
-        .bind(user_id)
-        .fetch_all(pool)
-        .await?
+    let (page, limit) = match wanted {
+        Suggestions::Pool(size) => (None, *size),
+        Suggestions::Page(page) => (Some(page), page.limit),
     };
 
-    println!("profiles {:?}", profiles);
+    let (age_min, age_max) = match &preferences.age_range {
+        Some(range) => (Some(range.min), Some(range.max)),
+        None => (None, None),
+    };
+
+    let ethnicity_filter =
+        dealbreaker_filter(preferences.ethnicity_preference.as_ref(), preferences.ethnicity_dealbreaker);
+    let religion_filter =
+        dealbreaker_filter(preferences.religion_preference.as_ref(), preferences.religion_dealbreaker);
+
+    let origin = get_location(pool, user_id).await?;
+    let earth_radius = distance_unit.earth_radius();
+    // distance_max only means something if we know where the user is
+    let distance_max = origin.and(preferences.distance_max);
+    let bbox = origin
+        .zip(distance_max)
+        .and_then(|(center, max)| bounding_box(center, max as f64, earth_radius));
+
+    let profiles = sqlx::query_as::<_, SuggestionProfile>(
+        r#"
+        SELECT 
+            p.user_id::TEXT AS user_id,
+            p.name,
+            p.bio,
+            p.birthdate::TEXT,
+            p.pronouns,
+            p.gender,
+            p.sexuality,
+            p.height,
+            p.job,
+            p.company,
+            p.school,
+            p.ethnicity,
+            p.politics,
+            p.religion,
+            p.relationship_type,
+            p.dating_intention,
+            p.drinks,
+            p.smokes,
+            CASE WHEN d.distance IS NOT NULL THEN GREATEST(1, ROUND(d.distance))::INT END AS distance,
+            u.last_active,
+            -- a page is ordered by the stored hash, the one the next cursor has to carry
+            COALESCE(fs.sort_hash, MD5(p.user_id::TEXT || $3)) AS sort_hash,
+            fs.score AS session_score,
+
+            CASE WHEN $18::UUID IS NOT NULL THEN COALESCE(
+                json_agg(
+                    json_build_object(
+                        'image_id', ui.id,
+                        'url', ui.url,
+                        'order', ui.display_order
+                    ) ORDER BY ui.display_order
+                ) FILTER (WHERE ui.id IS NOT NULL), 
+                '[]'
+            ) END AS images,
+
+            -- subquery rather than a join, which would multiply the image rows
+            CASE WHEN $18::UUID IS NOT NULL THEN COALESCE(
+                (
+                    SELECT json_agg(
+                        json_build_object(
+                            'id', up.id,
+                            'question', up.question,
+                            'answer', up.answer,
+                            'order', up.display_order
+                        ) ORDER BY up.display_order
+                    )
+                    FROM user_prompts up
+                    WHERE up.user_id = p.user_id
+                ),
+                '[]'
+            ) END AS prompts
+
+        FROM profiles p
+        INNER JOIN users u ON p.user_id = u.id
+        -- images and prompts only for a page ($18 session), ranking doesn't need them
+        LEFT JOIN user_images ui
+            ON p.user_id = ui.user_id AND ui.upload_status = 'ready' AND $18::UUID IS NOT NULL
+        LEFT JOIN feed_session_candidates fs
+            ON fs.session_id = $18 AND fs.viewer_id = $2 AND fs.candidate_id = p.user_id
+        LEFT JOIN profiles me ON me.user_id = $2
+        -- haversine distance from the user ($9 lat, $10 lng), NULL if either location is unknown
+        LEFT JOIN LATERAL (
+            SELECT $8::FLOAT8 * 2 * ASIN(LEAST(1.0, SQRT(
+                POWER(SIN(RADIANS(p.location[1] - $9::FLOAT8) / 2), 2)
+                + COS(RADIANS($9::FLOAT8)) * COS(RADIANS(p.location[1]))
+                  * POWER(SIN(RADIANS(p.location[0] - $10::FLOAT8) / 2), 2)
+            ))) AS distance
+        ) d ON TRUE
+        WHERE p.user_id != $2
+            AND u.is_profile_complete
+            -- a page: the session's candidates after the cursor ($19 score, $20 sort_hash)
+            AND ($18::UUID IS NULL OR fs.candidate_id IS NOT NULL)
+            AND ($19::FLOAT8 IS NULL OR fs.score < $19 OR (fs.score = $19 AND fs.sort_hash > $20))
+            AND ($1::TEXT[] IS NULL OR p.gender = ANY($1))
+            -- age in [min, max] <=> birthdate in (today - (max + 1) years, today - min years]
+            AND ($6::INT IS NULL OR p.birthdate <= CURRENT_DATE - make_interval(years => $6::INT))
+            AND ($7::INT IS NULL OR p.birthdate > CURRENT_DATE - make_interval(years => $7::INT + 1))
+            -- the box prefilter lets the GiST index on location do most of the work
+            AND ($12::FLOAT8 IS NULL
+                OR p.location <@ box(point($12::FLOAT8, $13::FLOAT8), point($14::FLOAT8, $15::FLOAT8)))
+            AND ($11::INT IS NULL OR d.distance <= $11::INT)
+            AND ($16::TEXT[] IS NULL OR p.ethnicity = ANY($16))
+            AND ($17::TEXT[] IS NULL OR p.religion = ANY($17))
+            -- reciprocal: the user fits the candidate's preferences too,
+            -- what the user hasn't filled in is not held against them
+            AND (u.pref_genders IS NULL OR me.gender IS NULL OR me.gender = ANY(u.pref_genders))
+            AND (u.pref_ethnicities IS NULL OR me.ethnicity IS NULL OR me.ethnicity = ANY(u.pref_ethnicities))
+            AND (u.pref_religions IS NULL OR me.religion IS NULL OR me.religion = ANY(u.pref_religions))
+            AND (u.pref_age_min IS NULL OR me.birthdate IS NULL
+                OR me.birthdate <= CURRENT_DATE - make_interval(years => u.pref_age_min))
+            AND (u.pref_age_max IS NULL OR me.birthdate IS NULL
+                OR me.birthdate > CURRENT_DATE - make_interval(years => u.pref_age_max + 1))
+            AND (u.pref_distance_max IS NULL OR d.distance IS NULL OR d.distance <= u.pref_distance_max)
+            -- already seen: liked or passed (passes come back in second look mode)
+            AND NOT EXISTS (
+                SELECT 1 FROM interactions i
+                WHERE i.from_user_id = $2 AND i.to_user_id = p.user_id
+                  AND NOT (
+                    $5::INT IS NOT NULL
+                    AND i.action = 'PASS'
+                    AND i.created_at < NOW() - make_interval(days => $5::INT)
+                  )
+            )
+            AND NOT EXISTS (
+                SELECT 1 FROM matches m
+                WHERE m.user1_id = LEAST(p.user_id, $2) AND m.user2_id = GREATEST(p.user_id, $2)
+            )
+            AND NOT EXISTS (
+                SELECT 1 FROM unmatches um
+                WHERE um.user1_id = LEAST(p.user_id, $2) AND um.user2_id = GREATEST(p.user_id, $2)
+            )
+            AND NOT EXISTS (
+                SELECT 1 FROM blocks b
+                WHERE (b.blocker_id = $2 AND b.blocked_id = p.user_id)
+                   OR (b.blocker_id = p.user_id AND b.blocked_id = $2)
+            )
+        GROUP BY 
+            p.user_id,
+            p.name,
+            p.bio,
+            p.birthdate,
+            p.pronouns,
+            p.gender,
+            p.sexuality,
+            p.height,
+            p.job,
+            p.company,
+            p.school,
+            p.ethnicity,
+            p.politics,
+            p.religion,
+            p.relationship_type,
+            p.dating_intention,
+            p.drinks,
+            p.smokes,
+            d.distance,
+            u.last_active,
+            fs.score,
+            fs.sort_hash
+
+            -- a page in ranked order; the pool (no fs row) with the most recently active first
+            ORDER BY fs.score DESC, fs.sort_hash, u.last_active DESC NULLS LAST, sort_hash
+            LIMIT $4
+            "#,
+    )
+    // ORDER BY RANDOM()
+    .bind(&preferences.gender_preference)
+    .bind(user_id)
+    .bind(seed)
+    .bind(limit)
+    .bind(second_look_days)
+    .bind(age_min)
+    .bind(age_max)
+    .bind(earth_radius)
+    .bind(origin.map(|o| o.lat))
+    .bind(origin.map(|o| o.lng))
+    .bind(distance_max)
+    .bind(bbox.map(|b| b.0))
+    .bind(bbox.map(|b| b.1))
+    .bind(bbox.map(|b| b.2))
+    .bind(bbox.map(|b| b.3))
+    .bind(ethnicity_filter)
+    .bind(religion_filter)
+    .bind(page.map(|page| page.session_id))
+    .bind(page.and_then(|page| page.after).map(|(score, _)| score))
+    .bind(page.and_then(|page| page.after).map(|(_, sort_hash)| sort_hash))
+    .fetch_all(pool)
+    .await?;
 
     Ok(profiles)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EARTH_RADIUS_KM: f64 = 6371.0;
+
+    fn contains(bbox: (f64, f64, f64, f64), lat: f64, lng: f64) -> bool {
+        let (min_lng, min_lat, max_lng, max_lat) = bbox;
+        (min_lat..=max_lat).contains(&lat) && (min_lng..=max_lng).contains(&lng)
+    }
+
+    #[test]
+    fn box_contains_the_points_at_the_distance() {
+        let center = GeoLocation { lat: 51.5074, lng: -0.1278 };
+        let bbox = bounding_box(center, 50.0, EARTH_RADIUS_KM).unwrap();
+
+        // 50 km due north / south is 50 / R radians of latitude
+        let d_lat = (50.0 / EARTH_RADIUS_KM).to_degrees();
+        assert!(contains(bbox, center.lat + d_lat * 0.999, center.lng));
+        assert!(contains(bbox, center.lat - d_lat * 0.999, center.lng));
+        // 50 km due east / west, at the center's latitude
+        let d_lng = d_lat / center.lat.to_radians().cos();
+        assert!(contains(bbox, center.lat, center.lng + d_lng * 0.999));
+        assert!(contains(bbox, center.lat, center.lng - d_lng * 0.999));
+        // and not much more
+        assert!(!contains(bbox, center.lat + d_lat * 1.01, center.lng));
+    }
+
+    #[test]
+    fn box_is_centered() {
+        let center = GeoLocation { lat: -33.8688, lng: 151.2093 };
+        let (min_lng, min_lat, max_lng, max_lat) = bounding_box(center, 100.0, EARTH_RADIUS_KM).unwrap();
+
+        assert!(((min_lat + max_lat) / 2.0 - center.lat).abs() < 1e-9);
+        assert!(((min_lng + max_lng) / 2.0 - center.lng).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_box_across_a_pole_or_the_antimeridian() {
+        let near_pole = GeoLocation { lat: 89.9, lng: 0.0 };
+        assert!(bounding_box(near_pole, 50.0, EARTH_RADIUS_KM).is_none());
+
+        let near_antimeridian = GeoLocation { lat: 0.0, lng: 179.9 };
+        assert!(bounding_box(near_antimeridian, 50.0, EARTH_RADIUS_KM).is_none());
+    }
+}
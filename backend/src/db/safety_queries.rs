@@ -0,0 +1,111 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Block a user (no-op if already blocked)
+pub async fn block_user(pool: &PgPool, blocker_id: &Uuid, blocked_id: &Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO blocks (blocker_id, blocked_id) VALUES ($1, $2) ON CONFLICT (blocker_id, blocked_id) DO NOTHING"
+    )
+    .bind(blocker_id)
+    .bind(blocked_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Remove a block. Returns false if there was no such block
+pub async fn unblock_user(pool: &PgPool, blocker_id: &Uuid, blocked_id: &Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM blocks WHERE blocker_id = $1 AND blocked_id = $2")
+        .bind(blocker_id)
+        .bind(blocked_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Get the ids of the users blocked by a user, most recent first
+pub async fn get_blocked_users(pool: &PgPool, blocker_id: &Uuid) -> Result<Vec<Uuid>, sqlx::Error> {
+    let rows: Vec<(Uuid,)> = sqlx::query_as(
+        "SELECT blocked_id FROM blocks WHERE blocker_id = $1 ORDER BY created_at DESC"
+    )
+    .bind(blocker_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.0).collect())
+}
+
+/// Check if either user blocked the other
+pub async fn is_blocked_between(pool: &PgPool, user_a: &Uuid, user_b: &Uuid) -> Result<bool, sqlx::Error> {
+    let row: Option<(Uuid,)> = sqlx::query_as(
+        r#"SELECT id FROM blocks
+           WHERE (blocker_id = $1 AND blocked_id = $2) OR (blocker_id = $2 AND blocked_id = $1)
+           LIMIT 1"#
+    )
+    .bind(user_a)
+    .bind(user_b)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.is_some())
+}
+
+/// Check that the reported content belongs to the reported user.
+/// For messages the reporter must also be part of the conversation
+pub async fn report_target_exists(
+    pool: &PgPool,
+    target_type: &str,
+    target_id: &Uuid,
+    reported_user_id: &Uuid,
+    reporter_id: &Uuid,
+) -> Result<bool, sqlx::Error> {
+    let query = match target_type {
+        "PHOTO" => "SELECT id FROM user_images WHERE id = $1 AND user_id = $2 AND upload_status = 'ready' AND $3::UUID IS NOT NULL",
+        "PROMPT" => "SELECT id FROM user_prompts WHERE id = $1 AND user_id = $2 AND $3::UUID IS NOT NULL",
+        "MESSAGE" => {
+            r#"SELECT msg.id FROM messages msg
+               INNER JOIN matches m ON m.id = msg.match_id
+               WHERE msg.id = $1 AND msg.sender_id = $2
+                 AND (m.user1_id = $3 OR m.user2_id = $3)"#
+        }
+        _ => return Ok(false),
+    };
+
+    let row: Option<(Uuid,)> = sqlx::query_as(query)
+        .bind(target_id)
+        .bind(reported_user_id)
+        .bind(reporter_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.is_some())
+}
+
+/// Store a report, returns its id
+pub async fn create_report(
+    pool: &PgPool,
+    reporter_id: &Uuid,
+    reported_user_id: &Uuid,
+    target_type: &str,
+    target_id: Option<&Uuid>,
+    category: &str,
+    details: Option<&str>,
+) -> Result<Uuid, sqlx::Error> {
+    let row: (Uuid,) = sqlx::query_as(
+        r#"INSERT INTO reports (reporter_id, reported_user_id, target_type, target_id, category, details)
+           VALUES ($1, $2, $3, $4, $5, $6)
+           RETURNING id"#
+    )
+    .bind(reporter_id)
+    .bind(reported_user_id)
+    .bind(target_type)
+    .bind(target_id)
+    .bind(category)
+    .bind(details)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.0)
+}
@@ -3,7 +3,7 @@
 use sqlx::PgPool;
 use uuid::Uuid;
 use serde_json::json;
-use crate::models::inputs::UpdateProfileRequest;
+use crate::models::inputs::{GeoLocation, UpdateProfileRequest};
 use crate::db::{user_queries, profile_queries, images_queries, prompt_queries};
 
 /// Sample profile data for seeding (based on data/profiles.ts)
@@ -1299,7 +1299,7 @@ fn to_profile_request(seed: &SeedProfile) -> UpdateProfileRequest {
         gender: Some(seed.gender.clone()),
         sexuality: Some(seed.sexuality.clone()),
         height: Some(seed.height),
-        location: Some(seed.location.clone()),
+        location: GeoLocation::parse(&seed.location),
         job: Some(seed.job.clone()),
         company: Some(seed.company.clone()),
         school: Some(seed.school.clone()),
@@ -1366,8 +1366,11 @@ pub async fn seed_database(pool: &PgPool) -> Result<(), sqlx::Error> {
         let existing_images = images_queries::count_images(pool, &user_uuid).await?;
         if existing_images == 0 {
             // Add 6 images using existing function
-            for image_url in &seed.images {
-                images_queries::upload_profile_images(pool, &user_uuid, image_url).await?;
+            for (slot, image_url) in seed.images.iter().enumerate() {
+                // Seed images are external URLs, nothing to confirm
+                let (image_id, _) =
+                    images_queries::upload_profile_images(pool, &user_uuid, slot as i32, image_url).await?;
+                images_queries::mark_image_ready(pool, &user_uuid, &image_id).await?;
             }
             println!("  📸 Added {} images", seed.images.len());
         }
@@ -1385,6 +1388,11 @@ pub async fn seed_database(pool: &PgPool) -> Result<(), sqlx::Error> {
         // Update preferences
         let preferences_json = to_preferences_json(&seed.preferences);
         update_user_preferences(pool, &user_uuid, preferences_json).await?;
+
+        // Finalize, like the app does, so the profile shows up in feeds
+        if profile_queries::profile_requirements_met(pool, &user_uuid).await? {
+            profile_queries::set_profile_complete(pool, &user_uuid).await?;
+        }
         
         if is_new {
             created_count += 1;
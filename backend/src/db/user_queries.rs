@@ -1,6 +1,8 @@
 use sqlx::{FromRow, PgPool};
 use uuid::Uuid;
 
+use crate::models::outputs::UserSummary;
+
 /// User struct for database queries
 #[derive(Debug, FromRow)]
 pub struct User {
@@ -86,12 +88,10 @@ pub async fn create_user(pool: &PgPool, phone: &str, email: &str, firebase_user_
 //     if let Some(id) = check_user_exists(pool, phone, email).await? {
 //         return Ok((id, false));
 //     }
-    
 //     // Create new user with both phone and email
 //     let id = create_user(pool, phone, email).await?;
 //     Ok((id, true))
 // }
-
 /// Create a new user for seeding purposes (with fake Firebase UID)
 /// Returns the new user's id
 pub async fn create_user_for_seed(pool: &PgPool, phone: &str, email: &str) -> Result<String, sqlx::Error> {
@@ -196,16 +196,32 @@ pub async fn get_user(pool: &PgPool, user_id: &Uuid) -> Result<Option<User>, sql
     Ok(user)
 }
 
+/// Get the summary (id + onboarding flags) of a user by ID
+pub async fn get_user_summary(pool: &PgPool, user_id: &Uuid) -> Result<Option<UserSummary>, sqlx::Error> {
+    let row: Option<(Uuid, bool, bool)> = sqlx::query_as(
+        "SELECT id, COALESCE(is_profile_complete, FALSE), COALESCE(is_new_user, FALSE) FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|(id, is_profile_complete, is_new_user)| UserSummary {
+        id: id.to_string(),
+        is_profile_complete,
+        is_new_user,
+    }))
+}
+
 /// Get user preferences as JSON
 pub async fn get_user_preferences(pool: &PgPool, user_id: &Uuid) -> Result<Option<serde_json::Value>, sqlx::Error> {
-    let row: Option<(serde_json::Value,)> = sqlx::query_as(
+    let row: Option<(Option<serde_json::Value>,)> = sqlx::query_as(
         "SELECT preferences FROM users WHERE id = $1"
     )
     .bind(user_id)
     .fetch_optional(pool)
     .await?;
 
-    Ok(row.map(|r| r.0))
+    Ok(row.and_then(|r| r.0))
 }
 
 /// Get user ID by email
@@ -223,6 +239,40 @@ pub async fn get_user_id_by_email(pool: &PgPool, email: Option<&str>) -> Result<
     Ok(None)
 }
 
+/// Get user ID by Firebase UID (works for email and phone sign-ins alike).
+/// Accounts created before firebase_uid was recorded (placeholder 'TEMP_<id>')
+/// are linked to the UID on first sight, matched by the token's email. Only a verified
+/// email links: anyone can get a token claiming an unverified address
+pub async fn get_user_id_by_firebase_uid(
+    pool: &PgPool,
+    firebase_uid: &str,
+    email: Option<&str>,
+    email_verified: Option<bool>,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let row: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM users WHERE firebase_uid = $1")
+        .bind(firebase_uid)
+        .fetch_optional(pool)
+        .await?;
+
+    if row.is_some() {
+        return Ok(row.map(|r| r.0));
+    }
+
+    let (Some(email), Some(true)) = (email, email_verified) else {
+        return Ok(None);
+    };
+
+    let row: Option<(Uuid,)> = sqlx::query_as(
+        r"UPDATE users SET firebase_uid = $1 WHERE email = $2 AND firebase_uid LIKE 'TEMP\_%' RETURNING id"
+    )
+    .bind(firebase_uid)
+    .bind(email)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| r.0))
+}
+
 /// Get user id and preferences by email or phone (serially)
 /// Returns (user_id, preferences) if found
 pub async fn get_user_with_preferences_by_identifier(
@@ -0,0 +1,150 @@
+use actix_web::{HttpResponse, ResponseError, http::StatusCode};
+use serde::Serialize;
+use std::fmt;
+
+/// Error returned by handlers. Clients get a stable `code` and a safe message;
+/// the details of internal errors are only logged
+#[derive(Debug)]
+pub enum ApiError {
+    Unauthorized,
+    /// Signed in, but not allowed to touch this resource
+    Forbidden(String),
+    NotFound(String),
+    /// The request is malformed as a whole (bad path param, cursor...)
+    BadRequest(String),
+    /// One or more fields failed validation
+    Validation(Vec<FieldError>),
+    Conflict(String),
+    Internal(anyhow::Error),
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// JSON body of every error; `status` and `message` match StatusResponse
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    status: &'static str,
+    code: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors: Option<&'a [FieldError]>,
+}
+
+impl ApiError {
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        ApiError::Forbidden(message.into())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ApiError::NotFound(message.into())
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        ApiError::BadRequest(message.into())
+    }
+
+    /// Validation error on a single field
+    pub fn invalid(field: impl Into<String>, message: impl Into<String>) -> Self {
+        ApiError::Validation(vec![FieldError {
+            field: field.into(),
+            message: message.into(),
+        }])
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        ApiError::Conflict(message.into())
+    }
+
+    /// For writes referencing another user: a foreign key violation means that user doesn't exist
+    pub fn user_not_found(error: sqlx::Error) -> Self {
+        match error {
+            sqlx::Error::Database(e) if e.is_foreign_key_violation() => ApiError::not_found("User not found"),
+            e => e.into(),
+        }
+    }
+
+    /// Stable, machine readable code for clients
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::Unauthorized => "UNAUTHORIZED",
+            ApiError::Forbidden(_) => "FORBIDDEN",
+            ApiError::NotFound(_) => "NOT_FOUND",
+            ApiError::BadRequest(_) => "BAD_REQUEST",
+            ApiError::Validation(_) => "VALIDATION_FAILED",
+            ApiError::Conflict(_) => "CONFLICT",
+            ApiError::Internal(_) => "INTERNAL",
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Unauthorized => write!(f, "Unauthorized"),
+            ApiError::Forbidden(message)
+            | ApiError::NotFound(message)
+            | ApiError::BadRequest(message)
+            | ApiError::Conflict(message) => write!(f, "{}", message),
+            // The first field error is the most useful one-line summary
+            ApiError::Validation(errors) => match errors.first() {
+                Some(error) => write!(f, "{}", error.message),
+                None => write!(f, "Invalid request"),
+            },
+            // Never shown to clients, see error_response
+            ApiError::Internal(_) => write!(f, "Internal server error"),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::BadRequest(_) | ApiError::Validation(_) => StatusCode::BAD_REQUEST,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if let ApiError::Internal(error) = self {
+            eprintln!("Internal error: {:?}", error);
+        }
+
+        let errors = match self {
+            ApiError::Validation(errors) => Some(errors.as_slice()),
+            _ => None,
+        };
+
+        HttpResponse::build(self.status_code()).json(ErrorBody {
+            status: "error",
+            code: self.code(),
+            message: self.to_string(),
+            errors,
+        })
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(error: sqlx::Error) -> Self {
+        match &error {
+            sqlx::Error::RowNotFound => ApiError::not_found("Not found"),
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                ApiError::conflict("Already exists")
+            }
+            _ => ApiError::Internal(error.into()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        ApiError::Internal(error)
+    }
+}
@@ -1,12 +1,18 @@
-use crate::r2_client::R2Client;
-use aws_sdk_s3::presigning::PresigningConfig;
+use super::object_store::ObjectStore;
 use bytes::Bytes;
 use chrono::Utc;
+use std::sync::Arc;
 use std::time::Duration;
 
+pub use super::object_store::ObjectInfo;
+
+/// How long upload and download URLs stay valid
+const URL_TTL: Duration = Duration::from_secs(3600); // 1 hour
+
+/// Uploads and download URLs over whichever `ObjectStore` is configured
 #[derive(Clone)]
 pub struct FileService {
-    r2_client: R2Client,
+    store: Arc<dyn ObjectStore>,
 }
 
 #[derive(serde::Serialize)]
@@ -35,8 +41,8 @@ pub struct ViewResponse {
 }
 
 impl FileService {
-    pub fn new(r2_client: R2Client) -> Self {
-        Self { r2_client }
+    pub fn new(store: Arc<dyn ObjectStore>) -> Self {
+        Self { store }
     }
 
     pub async fn upload_file(
@@ -49,17 +55,10 @@ impl FileService {
         let timestamp = Utc::now().timestamp_millis();
         let key = format!("uploads/{}-{}", timestamp, filename);
 
-        self.r2_client
-        .client
-        .put_object()
-        .bucket(&self.r2_client.bucket_name)
-        .key(&key)
-        .body(content.into())
-        .content_type(content_type)
-        .send()
-        .await?;
-
-        Ok(UploadResponse { key: key.clone(), url: format!("https://{}.r2.cloudflarestorage.com/{}", self.r2_client.bucket_name, key) })
+        self.store.put(&key, content, content_type).await?;
+        let url = self.store.presign_get(&key, URL_TTL).await?;
+
+        Ok(UploadResponse { key, url })
     }
 
     pub async fn upload_file_url(
@@ -69,59 +68,38 @@ impl FileService {
     ) -> anyhow::Result<SignedUrlResponse> {
         let timestamp = Utc::now().timestamp_millis();
         let key = format!("uploads/{}-{}", timestamp, filename);
-        let expires_in = 3600u64; // 1 hour
-
-        let presigning_config = PresigningConfig::expires_in(Duration::from_secs(expires_in))?;
-
-        let presigned_url = self.r2_client
-        .client
-        .put_object()
-        .bucket(&self.r2_client.bucket_name)
-        .key(&key)
-        .content_type(content_type)
-        .presigned(presigning_config)
-        .await?;
-
-        Ok(SignedUrlResponse {
-            upload_url: presigned_url.uri().to_string(),
-            key,
-        })
-    }
 
-    pub async fn download_file(&self, key: &str) -> anyhow::Result<DownloadResponse> {
-        // let expires_in = 3600u64; // 1 hour
-        // let presigning_config = PresigningConfig::expires_in(Duration::from_secs(expires_in))?;
+        let upload_url = self.store.presign_put(&key, content_type, URL_TTL).await?;
 
-        // let presigned_url = self.r2_client
-        //     .client
-        //     .get_object()
-        //     .bucket(&self.r2_client.bucket_name)
-        //     .key(key)
-        //     .presigned(presigning_config)
-        //     .await?;
+        Ok(SignedUrlResponse { upload_url, key })
+    }
 
-        let image_url = format!("https://pub-5ff7537b2d084834842634757b9cfd56.r2.dev/{}", key);
+    pub async fn download_file(&self, key: &str) -> anyhow::Result<DownloadResponse> {
+        let download_url = self.store.presign_get(key, URL_TTL).await?;
 
-        Ok(DownloadResponse {
-            download_url: image_url,
-        })
+        Ok(DownloadResponse { download_url })
     }
 
     pub async fn view_file(&self, key: &str) -> anyhow::Result<ViewResponse>{
-        let response = self.r2_client.client.get_object().bucket(&self.r2_client.bucket_name).key(key).send().await?;
-        let content_type = response.content_type.clone().unwrap_or_default();
-        let content_length = response.content_length.unwrap_or(0);
-        let body_bytes = response.body.collect().await?.into_bytes().to_vec();
+        let object = self
+            .store
+            .get(key)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No object at {}", key))?;
 
         Ok(ViewResponse {
-            content_type,
-            content_length,
-            body: body_bytes,
+            content_type: object.info.content_type.unwrap_or_default(),
+            content_length: object.info.size,
+            body: object.body.to_vec(),
         })
     }
 
+    /// Size and content type of an object, None if it doesn't exist
+    pub async fn head_file(&self, key: &str) -> anyhow::Result<Option<ObjectInfo>> {
+        self.store.head(key).await
+    }
+
     pub async fn delete_file(&self, key: &str) -> anyhow::Result<()> {
-        self.r2_client.client.delete_object().bucket(&self.r2_client.bucket_name).key(key).send().await?;
-        Ok(())
+        self.store.delete(key).await
     }
 }
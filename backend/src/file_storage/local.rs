@@ -0,0 +1,275 @@
+use actix_web::web;
+use async_trait::async_trait;
+use bytes::Bytes;
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use super::object_store::{ObjectInfo, ObjectStore, StoredObject};
+
+/// Object bodies live under `<root>/objects/<key>`, their content type under `<root>/meta/<key>`
+const OBJECTS_DIR: &str = "objects";
+const META_DIR: &str = "meta";
+
+/// Files on the local disk, for development and tests without a cloud account.
+/// The "presigned" URLs point back at this server (`/files/{token}`, see `routes::files`),
+/// the token is a signed grant for one key and method that expires like an S3 URL would
+pub struct LocalStore {
+    root: PathBuf,
+    /// Where clients reach this server, e.g. `http://localhost:8080`
+    base_url: String,
+    secret: Vec<u8>,
+}
+
+/// What a local URL allows: `method` on `key`, until `exp` (unix seconds)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UrlGrant {
+    pub key: String,
+    pub method: String,
+    /// Content type the upload has to be sent with (PUT only)
+    pub content_type: Option<String>,
+    exp: usize,
+}
+
+impl LocalStore {
+    pub fn new(root: impl Into<PathBuf>, base_url: &str, secret: &str) -> std::io::Result<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(root.join(OBJECTS_DIR))?;
+        std::fs::create_dir_all(root.join(META_DIR))?;
+
+        Ok(Self {
+            root,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            secret: secret.as_bytes().to_vec(),
+        })
+    }
+
+    /// The grant behind a URL token, None if it is forged, expired or for another method
+    pub fn verify(&self, token: &str, method: &str) -> Option<UrlGrant> {
+        let grant = decode::<UrlGrant>(token, &DecodingKey::from_secret(&self.secret), &Validation::default())
+            .ok()?
+            .claims;
+
+        (grant.method == method).then_some(grant)
+    }
+
+    fn sign(&self, key: &str, method: &str, content_type: Option<&str>, expires_in: Duration) -> anyhow::Result<String> {
+        let exp = chrono::Utc::now().timestamp() as usize + expires_in.as_secs() as usize;
+        let grant = UrlGrant {
+            key: key.to_string(),
+            method: method.to_string(),
+            content_type: content_type.map(str::to_string),
+            exp,
+        };
+        let token = encode(&Header::default(), &grant, &EncodingKey::from_secret(&self.secret))?;
+
+        Ok(format!("{}/files/{}", self.base_url, token))
+    }
+
+    /// Paths of the object and its metadata, refusing keys that would escape the root
+    fn paths(&self, key: &str) -> anyhow::Result<(PathBuf, PathBuf)> {
+        let relative = Path::new(key);
+        if key.is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            anyhow::bail!("Invalid object key {:?}", key);
+        }
+
+        Ok((self.root.join(OBJECTS_DIR).join(relative), self.root.join(META_DIR).join(relative)))
+    }
+}
+
+fn write_object(object: &Path, meta: &Path, body: &[u8], content_type: &str) -> std::io::Result<()> {
+    for path in [object, meta] {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    std::fs::write(object, body)?;
+    std::fs::write(meta, content_type)
+}
+
+fn read_info(object: &Path, meta: &Path) -> std::io::Result<Option<ObjectInfo>> {
+    match std::fs::metadata(object) {
+        Ok(metadata) => Ok(Some(ObjectInfo {
+            size: metadata.len() as i64,
+            content_type: std::fs::read_to_string(meta).ok(),
+        })),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Keys of every file under `dir`, `/`-separated relative to `base`
+fn collect_keys(base: &Path, dir: &Path, keys: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_keys(base, &path, keys)?;
+        } else if let Ok(relative) = path.strip_prefix(base) {
+            let parts: Vec<_> = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+            keys.push(parts.join("/"));
+        }
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl ObjectStore for LocalStore {
+    async fn put(&self, key: &str, body: Bytes, content_type: &str) -> anyhow::Result<()> {
+        let (object, meta) = self.paths(key)?;
+        let content_type = content_type.to_string();
+
+        web::block(move || write_object(&object, &meta, &body, &content_type)).await??;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Option<StoredObject>> {
+        let (object, meta) = self.paths(key)?;
+
+        let stored = web::block(move || -> std::io::Result<Option<StoredObject>> {
+            let Some(info) = read_info(&object, &meta)? else {
+                return Ok(None);
+            };
+            let body = Bytes::from(std::fs::read(&object)?);
+            Ok(Some(StoredObject { info, body }))
+        })
+        .await??;
+
+        Ok(stored)
+    }
+
+    async fn presign_put(&self, key: &str, content_type: &str, expires_in: Duration) -> anyhow::Result<String> {
+        self.paths(key)?;
+        self.sign(key, "PUT", Some(content_type), expires_in)
+    }
+
+    async fn presign_get(&self, key: &str, expires_in: Duration) -> anyhow::Result<String> {
+        self.paths(key)?;
+        self.sign(key, "GET", None, expires_in)
+    }
+
+    async fn head(&self, key: &str) -> anyhow::Result<Option<ObjectInfo>> {
+        let (object, meta) = self.paths(key)?;
+
+        Ok(web::block(move || read_info(&object, &meta)).await??)
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        let (object, meta) = self.paths(key)?;
+
+        web::block(move || {
+            remove_if_exists(&object)?;
+            remove_if_exists(&meta)
+        })
+        .await??;
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        let base = self.root.join(OBJECTS_DIR);
+        let prefix = prefix.to_string();
+
+        let mut keys = web::block(move || -> std::io::Result<Vec<String>> {
+            let mut keys = Vec::new();
+            collect_keys(&base, &base, &mut keys)?;
+            Ok(keys)
+        })
+        .await??;
+
+        keys.retain(|key| key.starts_with(&prefix));
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_URL: &str = "http://localhost:8080";
+
+    fn store(secret: &str) -> LocalStore {
+        let root = std::env::temp_dir().join(format!("local-store-test-{}", uuid::Uuid::new_v4()));
+        LocalStore::new(root, BASE_URL, secret).unwrap()
+    }
+
+    fn token(url: &str) -> &str {
+        url.strip_prefix(&format!("{}/files/", BASE_URL)).unwrap()
+    }
+
+    #[test]
+    fn paths_stay_under_the_root() {
+        let store = store("secret");
+
+        let (object, meta) = store.paths("uploads/123-photo.jpg").unwrap();
+
+        assert_eq!(object, store.root.join(OBJECTS_DIR).join("uploads/123-photo.jpg"));
+        assert_eq!(meta, store.root.join(META_DIR).join("uploads/123-photo.jpg"));
+    }
+
+    #[test]
+    fn paths_refuse_keys_escaping_the_root() {
+        let store = store("secret");
+
+        for key in ["", "..", "../secret", "uploads/../../secret", "./uploads/a.jpg", "/etc/passwd"] {
+            assert!(store.paths(key).is_err(), "accepted {:?}", key);
+        }
+    }
+
+    #[test]
+    fn verify_accepts_a_fresh_url_for_its_method() {
+        let store = store("secret");
+        let url = store.sign("uploads/a.jpg", "PUT", Some("image/jpeg"), Duration::from_secs(3600)).unwrap();
+
+        let grant = store.verify(token(&url), "PUT").unwrap();
+
+        assert_eq!(grant.key, "uploads/a.jpg");
+        assert_eq!(grant.content_type.as_deref(), Some("image/jpeg"));
+        assert!(store.verify(token(&url), "GET").is_none());
+    }
+
+    #[test]
+    fn verify_rejects_expired_urls() {
+        let store = store("secret");
+        // Past the default 60s leeway of the validation
+        let grant = UrlGrant {
+            key: "uploads/a.jpg".to_string(),
+            method: "GET".to_string(),
+            content_type: None,
+            exp: chrono::Utc::now().timestamp() as usize - 120,
+        };
+        let token = encode(&Header::default(), &grant, &EncodingKey::from_secret(b"secret")).unwrap();
+
+        assert!(store.verify(&token, "GET").is_none());
+    }
+
+    #[test]
+    fn verify_rejects_tampered_urls() {
+        let forged_url = store("other secret").sign("uploads/a.jpg", "GET", None, Duration::from_secs(3600)).unwrap();
+        let store = store("secret");
+        let url = store.sign("uploads/a.jpg", "GET", None, Duration::from_secs(3600)).unwrap();
+        let parts: Vec<&str> = token(&url).split('.').collect();
+
+        // Signed by another secret
+        assert!(store.verify(token(&forged_url), "GET").is_none());
+
+        // Claims swapped for another key, signature kept
+        let other_url = store.sign("uploads/b.jpg", "GET", None, Duration::from_secs(3600)).unwrap();
+        let other_claims = token(&other_url).split('.').nth(1).unwrap();
+        assert!(store.verify(&format!("{}.{}.{}", parts[0], other_claims, parts[2]), "GET").is_none());
+
+        // Signature altered
+        let mut signature = parts[2].to_string();
+        let flipped = if signature.starts_with('A') { "B" } else { "A" };
+        signature.replace_range(0..1, flipped);
+        assert!(store.verify(&format!("{}.{}.{}", parts[0], parts[1], signature), "GET").is_none());
+    }
+}
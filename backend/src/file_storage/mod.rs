@@ -1,3 +1,10 @@
 pub mod file_service;
+pub mod local;
+pub mod object_store;
+pub mod s3;
+pub mod upload_reaper;
 
-pub use file_service::{FileService, SignedUrlResponse, DownloadResponse, UploadResponse};
\ No newline at end of file
+pub use file_service::{FileService, ObjectInfo, SignedUrlResponse, DownloadResponse, UploadResponse};
+pub use local::LocalStore;
+pub use object_store::{ObjectStore, StoredObject};
+pub use s3::S3Store;
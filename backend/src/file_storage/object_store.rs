@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::time::Duration;
+
+/// Metadata of a stored object
+#[derive(Debug)]
+pub struct ObjectInfo {
+    pub size: i64,
+    pub content_type: Option<String>,
+}
+
+/// An object read back from the store
+pub struct StoredObject {
+    pub info: ObjectInfo,
+    pub body: Bytes,
+}
+
+/// Where uploaded files live. Keys are `/`-separated paths like `uploads/123-photo.jpg`.
+/// Implementations hand out URLs clients use directly, without going through the API
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    /// Store `body` under `key`, replacing any existing object
+    async fn put(&self, key: &str, body: Bytes, content_type: &str) -> anyhow::Result<()>;
+
+    /// Read an object, None if it doesn't exist
+    async fn get(&self, key: &str) -> anyhow::Result<Option<StoredObject>>;
+
+    /// URL the client can PUT the object to (with this exact content type) until `expires_in`
+    async fn presign_put(&self, key: &str, content_type: &str, expires_in: Duration) -> anyhow::Result<String>;
+
+    /// URL the client can GET the object from, valid for at least `expires_in`
+    async fn presign_get(&self, key: &str, expires_in: Duration) -> anyhow::Result<String>;
+
+    /// Size and content type of an object, None if it doesn't exist
+    async fn head(&self, key: &str) -> anyhow::Result<Option<ObjectInfo>>;
+
+    /// Delete an object, deleting a missing key is not an error
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
+
+    /// Keys starting with `prefix`
+    async fn list(&self, prefix: &str) -> anyhow::Result<Vec<String>>;
+}
@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use aws_sdk_s3::presigning::PresigningConfig;
+use bytes::Bytes;
+use std::time::Duration;
+
+use super::object_store::{ObjectInfo, ObjectStore, StoredObject};
+use crate::r2_client::R2Client;
+
+/// S3-compatible bucket (Cloudflare R2 in production)
+pub struct S3Store {
+    r2_client: R2Client,
+    /// Public base URL of the bucket (e.g. the r2.dev domain). Downloads use plain
+    /// public URLs when set, presigned ones otherwise
+    public_url: Option<String>,
+}
+
+impl S3Store {
+    pub fn new(r2_client: R2Client, public_url: Option<String>) -> Self {
+        Self {
+            r2_client,
+            public_url: public_url.map(|url| url.trim_end_matches('/').to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for S3Store {
+    async fn put(&self, key: &str, body: Bytes, content_type: &str) -> anyhow::Result<()> {
+        self.r2_client
+            .client
+            .put_object()
+            .bucket(&self.r2_client.bucket_name)
+            .key(key)
+            .body(body.into())
+            .content_type(content_type)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Option<StoredObject>> {
+        let response = self.r2_client.client.get_object().bucket(&self.r2_client.bucket_name).key(key).send().await;
+
+        let object = match response {
+            Ok(object) => object,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let info = ObjectInfo {
+            size: object.content_length().unwrap_or(0),
+            content_type: object.content_type().map(str::to_string),
+        };
+        let body = object.body.collect().await?.into_bytes();
+
+        Ok(Some(StoredObject { info, body }))
+    }
+
+    async fn presign_put(&self, key: &str, content_type: &str, expires_in: Duration) -> anyhow::Result<String> {
+        let presigned = self
+            .r2_client
+            .client
+            .put_object()
+            .bucket(&self.r2_client.bucket_name)
+            .key(key)
+            .content_type(content_type)
+            .presigned(PresigningConfig::expires_in(expires_in)?)
+            .await?;
+
+        Ok(presigned.uri().to_string())
+    }
+
+    async fn presign_get(&self, key: &str, expires_in: Duration) -> anyhow::Result<String> {
+        if let Some(public_url) = &self.public_url {
+            return Ok(format!("{}/{}", public_url, key));
+        }
+
+        let presigned = self
+            .r2_client
+            .client
+            .get_object()
+            .bucket(&self.r2_client.bucket_name)
+            .key(key)
+            .presigned(PresigningConfig::expires_in(expires_in)?)
+            .await?;
+
+        Ok(presigned.uri().to_string())
+    }
+
+    async fn head(&self, key: &str) -> anyhow::Result<Option<ObjectInfo>> {
+        let response = self.r2_client.client.head_object().bucket(&self.r2_client.bucket_name).key(key).send().await;
+
+        match response {
+            Ok(object) => Ok(Some(ObjectInfo {
+                size: object.content_length().unwrap_or(0),
+                content_type: object.content_type().map(str::to_string),
+            })),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.r2_client.client.delete_object().bucket(&self.r2_client.bucket_name).key(key).send().await?;
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        let mut keys = Vec::new();
+        let mut continuation_token = None;
+
+        loop {
+            let page = self
+                .r2_client
+                .client
+                .list_objects_v2()
+                .bucket(&self.r2_client.bucket_name)
+                .prefix(prefix)
+                .set_continuation_token(continuation_token)
+                .send()
+                .await?;
+
+            keys.extend(page.contents().iter().filter_map(|object| object.key().map(str::to_string)));
+
+            match page.next_continuation_token() {
+                Some(token) if page.is_truncated() == Some(true) => continuation_token = Some(token.to_string()),
+                _ => return Ok(keys),
+            }
+        }
+    }
+}
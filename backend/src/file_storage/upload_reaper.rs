@@ -0,0 +1,49 @@
+use sqlx::PgPool;
+use std::time::Duration;
+
+use crate::db::images_queries;
+use crate::file_storage::FileService;
+
+/// Spawn the task deleting uploads that stay pending past `ttl`: the presigned URL
+/// was handed out but the upload was never confirmed. Runs every `every`
+pub fn spawn(pool: PgPool, file_service: FileService, ttl: Duration, every: Duration) {
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(every);
+        loop {
+            interval.tick().await;
+
+            match reap(&pool, &file_service, ttl).await {
+                Ok(0) => {}
+                Ok(count) => println!("Upload reaper: removed {} pending uploads", count),
+                Err(e) => eprintln!("Upload reaper failed: {:?}", e),
+            }
+        }
+    });
+}
+
+/// Delete the stale pending uploads, rows first, returns how many went away
+async fn reap(pool: &PgPool, file_service: &FileService, ttl: Duration) -> anyhow::Result<u64> {
+    let stale = images_queries::get_stale_pending_images(pool, ttl.as_secs() as i64).await?;
+
+    let mut removed = 0;
+    for (image_id, key) in stale {
+        // Only if it is still pending, it may have been confirmed in the meantime.
+        // A confirm arriving now waits until the object is gone and finds no image
+        let mut tx = pool.begin().await?;
+        if !images_queries::claim_pending_image(&mut tx, &image_id).await? {
+            continue;
+        }
+
+        // Deleting a key that was never uploaded is fine, anything else is retried next run
+        if let Err(e) = file_service.delete_file(&key).await {
+            eprintln!("Upload reaper: failed to delete object {}: {:?}", key, e);
+            tx.rollback().await?;
+            continue;
+        }
+
+        tx.commit().await?;
+        removed += 1;
+    }
+
+    Ok(removed)
+}
@@ -0,0 +1,54 @@
+use actix_web::{FromRequest, HttpMessage, HttpRequest, dev::Payload, web};
+use firebase_auth::FirebaseUser;
+use futures::future::LocalBoxFuture;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::user_queries;
+use crate::error::ApiError;
+
+/// The signed-in user, resolved from the Firebase token (set by FirebaseAuthMiddleware)
+/// to the users row through `users.firebase_uid`.
+/// Responds 401 without a Firebase user and 404 when it has no account yet.
+/// Resolved once per request, later extractions reuse it
+#[derive(Clone)]
+pub struct CurrentUser {
+    pub id: Uuid,
+    pub firebase: FirebaseUser,
+}
+
+impl FromRequest for CurrentUser {
+    type Error = ApiError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+
+        Box::pin(async move {
+            if let Some(user) = req.extensions().get::<CurrentUser>() {
+                return Ok(user.clone());
+            }
+
+            let Some(firebase) = req.extensions().get::<FirebaseUser>().cloned() else {
+                return Err(ApiError::Unauthorized);
+            };
+
+            let Some(pool) = req.app_data::<web::Data<PgPool>>() else {
+                return Err(anyhow::anyhow!("PgPool is not registered as app data").into());
+            };
+
+            let id = user_queries::get_user_id_by_firebase_uid(
+                pool,
+                &firebase.user_id,
+                firebase.email.as_deref(),
+                firebase.email_verified,
+            )
+            .await?
+            .ok_or_else(|| ApiError::not_found("User not found"))?;
+
+            let user = CurrentUser { id, firebase };
+            req.extensions_mut().insert(user.clone());
+            Ok(user)
+        })
+    }
+}
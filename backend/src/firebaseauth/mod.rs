@@ -1,2 +1,5 @@
+pub mod current_user;
 pub mod verifier;
-pub mod middleware;
\ No newline at end of file
+pub mod middleware;
+
+pub use current_user::CurrentUser;
@@ -22,16 +22,20 @@ pub fn verify_request(
 
     // println!("Verifying token: {}", token);
 
-    // firebase.verify is NOT async - it's synchronous
-    let user = match firebase.verify(token) {
-        Ok(data) => data,
-        Err(e) => return Err(ErrorUnauthorized(format!("Invalid token: {:?}", e))),
-    };
-    
-    Ok(user)
+    verify_token(token, firebase)
 
     // match firebase_auth.verify(&token) {
     //     Err(e) => Err(ErrorUnauthorized(format!("Failed to verify Token {}", e))),
     //     Ok(user) => Ok(user),
     // }
 }
+
+/// Verify a raw Firebase ID token (used when the token doesn't come from the
+/// Authorization header, e.g. WebSocket clients passing it as a query param)
+pub fn verify_token(token: &str, firebase: &FirebaseAuth) -> Result<FirebaseUser, actix_web::Error> {
+    // firebase.verify is NOT async - it's synchronous
+    match firebase.verify(token) {
+        Ok(user) => Ok(user),
+        Err(e) => Err(ErrorUnauthorized(format!("Invalid token: {:?}", e))),
+    }
+}
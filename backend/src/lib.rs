@@ -5,6 +5,9 @@ pub mod models;
 pub mod routes;
 pub mod jwtauth;
 pub mod db;
+pub mod error;
 pub mod firebaseauth;
 pub mod file_storage;
 pub mod r2_client;
+pub mod ranking;
+pub mod realtime;
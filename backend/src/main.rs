@@ -9,20 +9,23 @@ use sqlx::postgres::PgPoolOptions;
 // use dotenv::dotenv;
 use std::collections::HashMap;
 use std::env;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use firebase_auth::{FirebaseAuth, FirebaseUser};
 
 mod db;
+mod error;
 mod file_storage;
 mod firebaseauth;
 mod jwtauth;
 mod models;
 mod r2_client;
+mod ranking;
+mod realtime;
 mod routes;
 
-use routes::{auth, feed, interactions, matches, profile, prompts, user};
+use routes::{auth, badges, feed, files, interactions, matches, profile, prompts, realtime as realtime_routes, safety, user};
 
 async fn health_check() -> impl Responder {
     HttpResponse::Ok().body("I'm ok")
@@ -60,35 +63,102 @@ async fn main() -> std::io::Result<()> {
         pending_verifications: Mutex::new(HashMap::new()),
     });
 
+    let feed_config = web::Data::new(models::state::FeedConfig::from_env());
+
     let firebase_auth = FirebaseAuth::new(&project_id).await;
     let app_firebase = web::Data::new(firebase_auth);
 
-    // R2 Storage Configuration
-    let r2_account_id = env::var("CLOUDFLARE_ACCOUNT_ID").unwrap_or_else(|_| {
-        println!("WARNING: CLOUDFLARE_ACCOUNT_ID not set, file uploads will not work");
-        String::new()
-    });
-    let r2_access_key = env::var("CLOUDFLARE_ACCESS_KEY_ID").unwrap_or_else(|_| {
-        println!("WARNING: CLOUDFLARE_ACCESS_KEY_ID not set, file uploads will not work");
-        String::new()
-    });
-    let r2_secret_key = env::var("CLOUDFLARE_SECRET_ACCESS_KEY").unwrap_or_else(|_| {
-        println!("WARNING: R2_SECRET_ACCESS_KEY not set, file uploads will not work");
-        String::new()
-    });
-    let r2_bucket_name = env::var("R2_BUCKET_NAME").unwrap_or_else(|_| {
-        println!("WARNING: R2_BUCKET_NAME not set, file uploads will not work");
-        String::new()
-    });
-
-    let r2_client = r2_client::R2Client::new(
-        &r2_account_id,
-        &r2_access_key,
-        &r2_secret_key,
-        &r2_bucket_name,
-    )
-    .await;
-    let file_service = web::Data::new(file_storage::FileService::new(r2_client));
+    // Object storage: R2 unless STORAGE_BACKEND=local, which keeps files in LOCAL_STORAGE_DIR
+    // and serves the upload/download URLs itself under /files. Local storage is opt-in only,
+    // a deploy missing its Cloudflare settings must not quietly write photos to its own disk
+    let use_r2 = match env::var("STORAGE_BACKEND").as_deref() {
+        Ok("r2") | Err(_) => true,
+        Ok("local") => false,
+        Ok(other) => panic!("Unknown STORAGE_BACKEND {}", other),
+    };
+
+    let mut local_store: Option<web::Data<file_storage::LocalStore>> = None;
+    let object_store: Arc<dyn file_storage::ObjectStore> = if use_r2 {
+        // R2 Storage Configuration
+        let r2_account_id = env::var("CLOUDFLARE_ACCOUNT_ID").unwrap_or_else(|_| {
+            println!("WARNING: CLOUDFLARE_ACCOUNT_ID not set, file uploads will not work");
+            String::new()
+        });
+        let r2_access_key = env::var("CLOUDFLARE_ACCESS_KEY_ID").unwrap_or_else(|_| {
+            println!("WARNING: CLOUDFLARE_ACCESS_KEY_ID not set, file uploads will not work");
+            String::new()
+        });
+        let r2_secret_key = env::var("CLOUDFLARE_SECRET_ACCESS_KEY").unwrap_or_else(|_| {
+            println!("WARNING: R2_SECRET_ACCESS_KEY not set, file uploads will not work");
+            String::new()
+        });
+        let r2_bucket_name = env::var("R2_BUCKET_NAME").unwrap_or_else(|_| {
+            println!("WARNING: R2_BUCKET_NAME not set, file uploads will not work");
+            String::new()
+        });
+        // Public bucket domain for image URLs, empty to hand out presigned downloads instead
+        let r2_public_url = env::var("R2_PUBLIC_URL")
+            .unwrap_or_else(|_| "https://pub-5ff7537b2d084834842634757b9cfd56.r2.dev".to_string());
+
+        let r2_client = r2_client::R2Client::new(
+            &r2_account_id,
+            &r2_access_key,
+            &r2_secret_key,
+            &r2_bucket_name,
+        )
+        .await;
+        Arc::new(file_storage::S3Store::new(
+            r2_client,
+            Some(r2_public_url).filter(|url| !url.is_empty()),
+        ))
+    } else {
+        let dir = env::var("LOCAL_STORAGE_DIR").unwrap_or_else(|_| "./storage".to_string());
+        // Must be reachable by the app, e.g. the LAN address when testing on a phone
+        let base_url = env::var("LOCAL_STORAGE_URL").unwrap_or_else(|_| format!("http://localhost:{}", port));
+        // Signs the URLs, anyone who knows it can read and write any stored file
+        let secret = env::var("LOCAL_STORAGE_SECRET")
+            .ok()
+            .filter(|secret| !secret.is_empty())
+            .expect("LOCAL_STORAGE_SECRET MUST BE SET when STORAGE_BACKEND=local");
+
+        println!("Storing files locally in {} (served from {}/files)", dir, base_url);
+        let store = Arc::new(
+            file_storage::LocalStore::new(&dir, &base_url, &secret)
+                .expect("Failed to create LOCAL_STORAGE_DIR"),
+        );
+        local_store = Some(web::Data::from(store.clone()));
+        store
+    };
+    let file_service = web::Data::new(file_storage::FileService::new(object_store));
+
+    // Uploads that are never confirmed get cleaned up in the background
+    let upload_config = models::state::UploadConfig::from_env();
+    file_storage::upload_reaper::spawn(
+        pool.clone(),
+        file_service.get_ref().clone(),
+        upload_config.pending_ttl,
+        upload_config.reap_every,
+    );
+
+    // Realtime fan-out: "postgres" shares events between instances via LISTEN/NOTIFY,
+    // anything else keeps them in this process (fine for a single node)
+    let event_hub: Arc<dyn realtime::EventHub> = match env::var("REALTIME_HUB").as_deref() {
+        Ok("postgres") => Arc::new(
+            realtime::PgNotifyHub::start(pool.clone())
+                .await
+                .expect("Failed to start Postgres realtime hub"),
+        ),
+        _ => Arc::new(realtime::InMemoryHub::new()),
+    };
+    let event_hub: web::Data<dyn realtime::EventHub> = web::Data::from(event_hub);
+
+    // Feed ranking: FEED_RANKER picks "recency", "overlap", "random" or "weighted" (default,
+    // mixed with FEED_RANKER_WEIGHTS, e.g. "recency=0.6,overlap=0.3,random=0.1")
+    let feed_ranker: Arc<dyn ranking::Ranker> = match env::var("FEED_RANKER").as_deref() {
+        Ok("weighted") | Err(_) => Arc::new(ranking::WeightedRanker::from_env()),
+        Ok(name) => Arc::from(ranking::by_name(name).expect("Unknown FEED_RANKER")),
+    };
+    let feed_ranker: web::Data<dyn ranking::Ranker> = web::Data::from(feed_ranker);
 
     // sqlx::migrate!("./migrations")
     //     .run(&pool)
@@ -106,6 +176,26 @@ async fn main() -> std::io::Result<()> {
             .wrap(Cors::permissive())
             .route("/test", web::get().to(health_check))
             .route("/health", web::get().to(health_check))
+            // WebSocket - registered before the /api/v1 scope since it checks the
+            // Firebase token itself (browsers can't set headers on WebSockets)
+            .service(
+                web::resource("/api/v1/ws")
+                    .app_data(web::Data::new(pool.clone()))
+                    .app_data(event_hub.clone())
+                    .route(web::get().to(realtime_routes::connect)),
+            )
+            // Local storage URLs - authorized by the signed token in the path, not Firebase
+            .configure(|cfg| {
+                if let Some(store) = &local_store {
+                    cfg.service(
+                        web::resource("/files/{token}")
+                            .app_data(store.clone())
+                            .app_data(web::PayloadConfig::new(models::inputs::MAX_IMAGE_BYTES as usize))
+                            .route(web::put().to(files::upload))
+                            .route(web::get().to(files::download)),
+                    );
+                }
+            })
             // Protected routes (auth required) - wrapped in a scope with middleware
             .service(
                 web::scope("/api/v1")
@@ -113,6 +203,13 @@ async fn main() -> std::io::Result<()> {
                     .wrap(firebaseauth::middleware::FirebaseAuthMiddleware)
                     .app_data(web::Data::new(pool.clone()))
                     .app_data(file_service.clone())
+                    .app_data(event_hub.clone())
+                    .app_data(feed_config.clone())
+                    .app_data(feed_ranker.clone())
+                    // Malformed bodies (e.g. an unknown action) get the ApiError body too
+                    .app_data(web::JsonConfig::default().error_handler(|e, _| {
+                        error::ApiError::bad_request(e.to_string()).into()
+                    }))
                     .route("/test", web::get().to(health_check)) // Test route in /api/v1 scope
                     .route("/user/create", web::post().to(user::create_user))
                     .route("/user/check", web::post().to(user::check_user_exists))
@@ -130,6 +227,18 @@ async fn main() -> std::io::Result<()> {
                     )
                     
                     .route("/files/upload-url", web::post().to(profile::get_upload_url))
+                    .route(
+                        "/profile/images/order",
+                        web::put().to(profile::reorder_images),
+                    )
+                    .route(
+                        "/profile/images/{id}/confirm",
+                        web::post().to(profile::confirm_upload),
+                    )
+                    .route(
+                        "/profile/images/{id}",
+                        web::delete().to(profile::delete_image),
+                    )
                     
                     .route(
                         "/files/download-url",
@@ -142,12 +251,9 @@ async fn main() -> std::io::Result<()> {
                     .route("/profile", web::delete().to(profile::delete_account))
                     .route("/feed", web::get().to(feed::get_feed))
                     .route("/interact", web::post().to(interactions::interact))
+                    .route("/likes", web::get().to(interactions::get_likes)) // who liked me
                     .route(
-                        "/interact/me/{user_id}",
-                        web::post().to(interactions::get_interactions_for_me),
-                    ) // who inacted to me
-                    .route(
-                        "/interact/to/{user_id}",
+                        "/interact/to",
                         web::post().to(interactions::get_interactions_to_everyone),
                     )
                     .route("/matches", web::get().to(matches::get_matches))
@@ -159,6 +265,14 @@ async fn main() -> std::io::Result<()> {
                         "/matches/{id}/messages",
                         web::post().to(matches::send_message),
                     )
+                    .route("/matches/{id}", web::delete().to(matches::unmatch))
+                    .route("/matches/{id}/read", web::post().to(matches::mark_read))
+                    .route("/badges", web::get().to(badges::get_badges))
+                    // Safety routes
+                    .route("/blocks", web::get().to(safety::get_blocked_users))
+                    .route("/blocks", web::post().to(safety::block_user))
+                    .route("/blocks/{user_id}", web::delete().to(safety::unblock_user))
+                    .route("/reports", web::post().to(safety::report))
                     // Prompts routes
                     .route("/prompts", web::get().to(prompts::get_prompts))
                     .route("/prompts", web::post().to(prompts::create_prompt))
@@ -193,6 +307,15 @@ POST /profile
 POST /profile/images
 - Uploads a user profile image.
 
+POST /files/upload-url, POST /profile/images/{id}/confirm
+- Presigned upload of a profile photo; it shows once the upload is confirmed.
+
+PUT /profile/images/order
+- Moves the profile photos to new slots in one go.
+
+DELETE /profile/images/{id}
+- Deletes a profile photo and its stored object; the later photos move up a slot.
+
 POST /profile/finalize
 - Finalizes profile (sets "is_profile_complete") after ensuring 6 images are present.
 
@@ -205,6 +328,9 @@ GET /feed
 POST /interact
 - Handles Like (Heart) or Pass (Cross) interactions.
 
+GET /likes
+- The "Likes You" inbox: unanswered likes to the caller, with the liked content.
+
 GET /matches
 - Gets a list of all matches (conversations).
 
@@ -213,4 +339,25 @@ GET /matches/{id}/messages
 
 POST /matches/{id}/messages
 - Sends a new message to a match.
+
+DELETE /matches/{id}
+- Unmatches: removes the match and its messages, optional reason for analytics.
+
+POST /matches/{id}/read
+- Marks the messages of a match as read up to a given message.
+
+GET /badges
+- Unread message and pending like counters for the tab badges.
+
+POST /blocks, DELETE /blocks/{user_id}, GET /blocks
+- Blocks / unblocks a user; blocked pairs are hidden from each other everywhere.
+
+POST /reports
+- Reports a profile, photo, prompt or message.
+
+PUT /files/{token}, GET /files/{token}
+- Only with the local storage backend: targets of its presigned upload / download URLs.
+
+GET /ws
+- WebSocket pushing new messages, matches and likes; also accepts message sends.
 */
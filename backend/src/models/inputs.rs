@@ -1,4 +1,4 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Deserialize)]
 pub struct PhoneLoginRequest {
@@ -39,7 +39,8 @@ pub struct UpdateProfileRequest {
     pub gender: Option<String>,    // "Woman", "Man", "Non-binary"
     pub sexuality: Option<String>, // "Straight", "Gay", "Bisexual"
     pub height: Option<i32>,       // in cm
-    pub location: Option<String>,  // Will need to convert to POINT
+    #[serde(default, deserialize_with = "deserialize_location")]
+    pub location: Option<GeoLocation>,
     pub job: Option<String>,
     pub company: Option<String>,
     pub school: Option<String>,
@@ -52,25 +53,123 @@ pub struct UpdateProfileRequest {
     pub smokes: Option<String>,            // "No", "Yes"
 }
 
+/// Coordinates in degrees, stored as POINT(lng, lat)
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct GeoLocation {
+    pub lat: f64,
+    pub lng: f64,
+}
+
+impl GeoLocation {
+    pub fn is_valid(&self) -> bool {
+        (-90.0..=90.0).contains(&self.lat) && (-180.0..=180.0).contains(&self.lng)
+    }
+
+    /// Parse a "lat,lng" string
+    pub fn parse(location: &str) -> Option<Self> {
+        let (lat, lng) = location.split_once(',')?;
+        Some(GeoLocation {
+            lat: lat.trim().parse().ok()?,
+            lng: lng.trim().parse().ok()?,
+        })
+    }
+}
+
+/// `{lat, lng}`, or a string from app versions that still send the location as text:
+/// "lat,lng" is accepted, anything else (a city name) leaves the location unchanged
+fn deserialize_location<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<GeoLocation>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum LocationInput {
+        Point(GeoLocation),
+        Text(String),
+    }
+
+    Ok(match Option::<LocationInput>::deserialize(deserializer)? {
+        Some(LocationInput::Point(location)) => Some(location),
+        Some(LocationInput::Text(text)) => GeoLocation::parse(&text),
+        None => None,
+    })
+}
+
 #[derive(Deserialize)]
 pub struct InteractRequest {
     pub target_user_id: String,
-    pub action: String, // "LIKE" or "PASS" (Cross Click)
+    pub action: InteractAction,
     pub context: Option<InteractContext>,
-    pub comment: Option<String>,
+    pub comment: Option<String>, // only on LIKE
 }
 
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum InteractAction {
+    Like,
+    Pass, // Cross Click
+}
+
+impl InteractAction {
+    /// Value stored in interactions.action
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InteractAction::Like => "LIKE",
+            InteractAction::Pass => "PASS",
+        }
+    }
+}
+
+/// The image or prompt of the target that was liked
 #[derive(Deserialize)]
 pub struct InteractContext {
-    pub r#type: String, // "IMAGE" or "PROMPT"
+    pub r#type: InteractContextType,
     pub id: String,
 }
 
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum InteractContextType {
+    Image,
+    Prompt,
+}
+
+impl InteractContextType {
+    /// Value stored in interactions.context_type
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InteractContextType::Image => "IMAGE",
+            InteractContextType::Prompt => "PROMPT",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BlockRequest {
+    pub user_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct ReportRequest {
+    pub reported_user_id: String,
+    pub target_type: String,       // "PROFILE", "PHOTO", "PROMPT" or "MESSAGE"
+    pub target_id: Option<String>, // required unless target_type is "PROFILE"
+    pub category: String,          // "SPAM", "HARASSMENT", "INAPPROPRIATE", ...
+    pub details: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct SendMessageRequest {
     pub text: String,
 }
 
+#[derive(Deserialize)]
+pub struct UnmatchRequest {
+    pub reason: Option<String>, // optional, kept for analytics
+}
+
+#[derive(Deserialize)]
+pub struct MarkReadRequest {
+    pub message_id: String, // everything up to this message is marked read
+}
+
 #[derive(Deserialize)]
 pub struct CreatePromptRequest {
     pub question: String,
@@ -84,6 +183,11 @@ pub struct UpdatePromptRequest {
 }
 
 // USERS PREFERENCES
+/// Youngest age allowed on the app (profiles and age range preferences)
+pub const MIN_AGE: i32 = 18;
+/// Oldest age accepted in an age range preference
+pub const MAX_AGE: i32 = 100;
+
 #[derive(Deserialize, Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AgeRange {
@@ -91,7 +195,7 @@ pub struct AgeRange {
     pub max: i32,
 }
 
-#[derive(Deserialize, Debug, Clone, Serialize)]
+#[derive(Deserialize, Debug, Clone, Default, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Preferences {
     // User identification (at least one required to find the user)
@@ -101,14 +205,29 @@ pub struct Preferences {
     pub age_range: Option<AgeRange>,
     pub distance_max: Option<i32>,
     pub gender_preference: Option<Vec<String>>,
-    pub ethnicity_preference: Option<Vec<String>>,
+    pub ethnicity_preference: Option<Vec<String>>, // empty = open to all
     pub religion_preference: Option<Vec<String>>,
+    // false = nice-to-have: matching profiles rank higher instead of filtering (default true)
+    pub ethnicity_dealbreaker: Option<bool>,
+    pub religion_dealbreaker: Option<bool>,
 }
 
+/// Largest profile photo accepted, in bytes
+pub const MAX_IMAGE_BYTES: i64 = 10 * 1024 * 1024;
+/// Content types accepted for profile photos
+pub const IMAGE_CONTENT_TYPES: [&str; 4] = ["image/jpeg", "image/png", "image/webp", "image/heic"];
+
 #[derive(Deserialize)]
 pub struct UploadUrlRequest {
     pub filename: String,
     pub content_type: String,
+    pub slot: i32, // 0 to 5, the photo there is replaced once the upload is confirmed
+}
+
+/// The user's image ids in their new order, first one in slot 0
+#[derive(Deserialize)]
+pub struct ReorderImagesRequest {
+    pub image_ids: Vec<String>,
 }
 
 #[derive(Deserialize)]
@@ -24,7 +24,7 @@ pub struct AuthResponse {
     pub user: UserSummary,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct UserSummary {
     pub id: String,
     pub is_profile_complete: bool,
@@ -38,6 +38,15 @@ pub struct UserProfile {
     pub images: Option<Vec<UserImage>>,
     pub prompts: Option<Vec<UserPrompt>>,
     pub details: Option<ProfileDetails>,
+    // Only set in the feed; raw coordinates are never shared with other users
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance: Option<Distance>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Distance {
+    pub value: i32,   // rounded, at least 1
+    pub unit: String, // "km" or "mi"
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -48,7 +57,7 @@ pub struct UserImage {
     pub order: i32,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct UserPrompt {
     pub id: String,
     pub question: String,
@@ -65,7 +74,7 @@ pub struct ProfileDetails {
     pub gender: Option<String>,    // "Woman", "Man", "Non-binary"
     pub sexuality: Option<String>, // "Straight", "Gay", "Bisexual"
     pub height: Option<i32>,       // in cm
-    pub location: Option<String>,  // Will need to convert to POINT
+    pub location: Option<String>,  // "lat,lng", only on the user's own profile
     pub job: Option<String>,
     pub company: Option<String>,
     pub school: Option<String>,
@@ -79,7 +88,7 @@ pub struct ProfileDetails {
 }
 
 /// Profile with user_id for feed suggestions
-#[derive(Serialize, Debug, sqlx::FromRow)]
+#[derive(Serialize, Debug, Default, sqlx::FromRow)]
 pub struct SuggestionProfile {
     pub user_id: String,
     pub name: Option<String>,
@@ -89,7 +98,6 @@ pub struct SuggestionProfile {
     pub gender: Option<String>,
     pub sexuality: Option<String>,
     pub height: Option<i32>,
-    pub location: Option<String>,
     pub job: Option<String>,
     pub company: Option<String>,
     pub school: Option<String>,
@@ -100,7 +108,24 @@ pub struct SuggestionProfile {
     pub dating_intention: Option<String>,
     pub drinks: Option<String>,
     pub smokes: Option<String>,
+    pub distance: Option<i32>, // in FeedConfig::distance_unit
     pub images: Option<serde_json::Value>,
+    pub prompts: Option<serde_json::Value>,
+    // Ranking inputs, not part of the response
+    #[serde(skip_serializing)]
+    pub last_active: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(skip_serializing)]
+    pub sort_hash: String, // MD5 of the user id and the feed session seed
+    #[serde(skip_serializing)]
+    pub session_score: Option<f64>, // score stored in the feed session, pages only
+}
+
+/// Where to PUT the photo, then confirm it with POST /profile/images/{image_id}/confirm
+#[derive(Serialize)]
+pub struct UploadUrlResponse {
+    pub upload_url: String,
+    pub key: String,
+    pub image_id: String,
 }
 
 #[derive(Serialize)]
@@ -122,6 +147,7 @@ pub struct FinalizeProfileResponse {
 #[derive(Serialize)]
 pub struct FeedResponse {
     pub profiles: Vec<UserProfile>,
+    pub next_cursor: Option<String>, // None once the feed is exhausted
 }
 
 // Interactions
@@ -142,7 +168,10 @@ pub struct MatchData {
 pub struct MatchSummary {
     pub id: String,
     pub with_user: UserSummary,
+    pub name: Option<String>,
+    pub photo_url: Option<String>, // first photo of the other user
     pub last_message: Option<MessagePreview>,
+    pub unread_count: i64,
 }
 
 #[derive(Serialize)]
@@ -157,12 +186,44 @@ pub struct MessageHistoryResponse {
     pub messages: Vec<Message>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Message {
     pub id: String,
     pub sender_id: String,
     pub text: String,
     pub created_at: String,
+    pub is_read: bool,
+}
+
+#[derive(Serialize)]
+pub struct MatchesResponse {
+    pub matches: Vec<MatchSummary>,
+    pub total_unread: i64,
+}
+
+#[derive(Serialize)]
+pub struct MarkReadResponse {
+    pub marked: u64,
+}
+
+// Safety
+#[derive(Serialize)]
+pub struct BlockedUsersResponse {
+    pub user_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ReportResponse {
+    pub status: String,
+    pub report_id: String,
+}
+
+// Badges
+#[derive(Serialize)]
+pub struct BadgesResponse {
+    pub unread_messages: i64,
+    pub unread_conversations: i64,
+    pub pending_likes: i64,
 }
 
 #[derive(Serialize)]
@@ -170,6 +231,38 @@ pub struct SuccessResponse {
     pub success: bool,
 }
 
+// Likes inbox
+#[derive(Serialize)]
+pub struct LikesResponse {
+    pub likes: Vec<LikeCard>,
+}
+
+/// Someone who liked the caller and is waiting for an answer
+#[derive(Serialize)]
+pub struct LikeCard {
+    pub id: String, // interaction id, also the pagination cursor
+    pub user: LikerProfile,
+    pub liked_content: Option<LikedContent>, // None when the whole profile was liked
+    pub comment: Option<String>,
+    pub created_at: String, // ISO String
+}
+
+#[derive(Serialize)]
+pub struct LikerProfile {
+    pub id: String,
+    pub name: Option<String>,
+    pub birthdate: Option<String>,
+    pub photo_url: Option<String>, // first photo
+}
+
+/// The image or prompt of the caller that was liked
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "UPPERCASE")]
+pub enum LikedContent {
+    Image { id: String, url: String },
+    Prompt { id: String, question: String, answer: String },
+}
+
 #[derive(Deserialize, Serialize, FromRow, Debug)]
 pub struct Interaction {
     pub id: Uuid,
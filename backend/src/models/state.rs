@@ -1,6 +1,93 @@
 use std::sync::Mutex;
 use std::collections::HashMap;
+use std::time::Duration;
 
 pub struct AppState {
     pub pending_verifications: Mutex<HashMap<String, String>>,
-}  
\ No newline at end of file
+}
+
+/// Feed tuning, read from the environment at startup
+#[derive(Debug, Clone)]
+pub struct FeedConfig {
+    /// Passed profiles come back in "second look" mode after this many days
+    pub second_look_days: i32,
+    /// Unit of `Preferences::distance_max` and of the distances in the feed
+    pub distance_unit: DistanceUnit,
+    /// Most candidates a new feed session ranks and stores
+    pub session_pool_size: i64,
+}
+
+/// Photo upload tuning, read from the environment at startup
+#[derive(Debug, Clone)]
+pub struct UploadConfig {
+    /// Uploads not confirmed after this long are deleted (the presigned URL lasts 1 hour)
+    pub pending_ttl: Duration,
+    /// How often the reaper looks for them
+    pub reap_every: Duration,
+}
+
+impl UploadConfig {
+    pub fn from_env() -> Self {
+        let ttl_minutes = std::env::var("PENDING_UPLOAD_TTL_MINUTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|minutes: &u64| *minutes > 0)
+            .unwrap_or(120);
+
+        Self {
+            pending_ttl: Duration::from_secs(ttl_minutes * 60),
+            reap_every: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceUnit {
+    Km,
+    Miles,
+}
+
+impl DistanceUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DistanceUnit::Km => "km",
+            DistanceUnit::Miles => "mi",
+        }
+    }
+
+    /// Mean earth radius in this unit, used for haversine distances
+    pub fn earth_radius(&self) -> f64 {
+        match self {
+            DistanceUnit::Km => 6371.0,
+            DistanceUnit::Miles => 3958.8,
+        }
+    }
+}
+
+impl FeedConfig {
+    pub fn from_env() -> Self {
+        let second_look_days = std::env::var("FEED_SECOND_LOOK_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|days: &i32| *days > 0)
+            .unwrap_or(30);
+
+        // DISTANCE_UNIT=mi (or miles) switches to miles, km otherwise
+        let distance_unit = match std::env::var("DISTANCE_UNIT").as_deref() {
+            Ok("mi") | Ok("miles") => DistanceUnit::Miles,
+            _ => DistanceUnit::Km,
+        };
+
+        let session_pool_size = std::env::var("FEED_SESSION_POOL_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|size: &i64| *size > 0)
+            .unwrap_or(500);
+
+        Self {
+            second_look_days,
+            distance_unit,
+            session_pool_size,
+        }
+    }
+}
@@ -0,0 +1,21 @@
+pub mod overlap;
+pub mod random;
+pub mod ranker;
+pub mod recency;
+pub mod weighted;
+
+pub use overlap::PreferenceOverlapRanker;
+pub use random::RandomRanker;
+pub use ranker::{Ranker, RankingContext, ScoredCandidate};
+pub use recency::RecencyRanker;
+pub use weighted::WeightedRanker;
+
+/// Built-in single-signal rankers by config name ("recency", "overlap" or "random")
+pub fn by_name(name: &str) -> Option<Box<dyn Ranker>> {
+    match name {
+        "recency" => Some(Box::new(RecencyRanker)),
+        "overlap" => Some(Box::new(PreferenceOverlapRanker)),
+        "random" => Some(Box::new(RandomRanker)),
+        _ => None,
+    }
+}
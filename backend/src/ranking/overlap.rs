@@ -0,0 +1,35 @@
+use crate::models::outputs::SuggestionProfile;
+use crate::ranking::{Ranker, RankingContext};
+
+/// Share of the viewer's list preferences (gender, ethnicity, religion) the candidate fits.
+/// Dealbreakers are already filtered in SQL, so this mostly rewards nice-to-haves
+pub struct PreferenceOverlapRanker;
+
+impl Ranker for PreferenceOverlapRanker {
+    fn score(&self, candidate: &SuggestionProfile, context: &RankingContext) -> f64 {
+        let preferences = context.preferences;
+        let checks = [
+            (&preferences.gender_preference, &candidate.gender),
+            (&preferences.ethnicity_preference, &candidate.ethnicity),
+            (&preferences.religion_preference, &candidate.religion),
+        ];
+
+        let mut considered = 0;
+        let mut matched = 0;
+        for (wanted, value) in checks {
+            // Empty = open to all, nothing to compare
+            let Some(wanted) = wanted.as_ref().filter(|w| !w.is_empty()) else {
+                continue;
+            };
+            considered += 1;
+            if value.as_ref().is_some_and(|v| wanted.contains(v)) {
+                matched += 1;
+            }
+        }
+
+        if considered == 0 {
+            return 0.0;
+        }
+        matched as f64 / considered as f64
+    }
+}
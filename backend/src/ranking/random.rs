@@ -0,0 +1,16 @@
+use crate::models::outputs::SuggestionProfile;
+use crate::ranking::{Ranker, RankingContext};
+
+/// Baseline: a shuffle that stays the same for the whole feed session.
+/// Reads the seeded MD5 (`sort_hash`) computed by `get_suggestions`
+pub struct RandomRanker;
+
+impl Ranker for RandomRanker {
+    fn score(&self, candidate: &SuggestionProfile, _context: &RankingContext) -> f64 {
+        candidate
+            .sort_hash
+            .get(..8)
+            .and_then(|prefix| u32::from_str_radix(prefix, 16).ok())
+            .map_or(0.0, |value| value as f64 / u32::MAX as f64)
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::models::inputs::Preferences;
+use crate::models::outputs::SuggestionProfile;
+
+/// What a ranker knows about the viewer
+pub struct RankingContext<'a> {
+    pub viewer_id: &'a Uuid,
+    pub preferences: &'a Preferences,
+    /// Start of the feed session, used instead of the clock so every page scores alike
+    pub now: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct ScoredCandidate {
+    pub profile: SuggestionProfile,
+    pub score: f64,
+}
+
+/// Orders the feed candidates returned by `get_suggestions`.
+/// Scores are in [0, 1], higher comes first
+pub trait Ranker: Send + Sync {
+    fn score(&self, candidate: &SuggestionProfile, context: &RankingContext) -> f64;
+
+    /// Score every candidate, best first; ties keep the session shuffle (`sort_hash`)
+    fn rank(&self, candidates: Vec<SuggestionProfile>, context: &RankingContext) -> Vec<ScoredCandidate> {
+        let mut scored: Vec<ScoredCandidate> = candidates
+            .into_iter()
+            .map(|profile| ScoredCandidate {
+                score: self.score(&profile, context),
+                profile,
+            })
+            .collect();
+
+        scored.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.profile.sort_hash.cmp(&b.profile.sort_hash))
+        });
+        scored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scores a candidate by its height, in percent
+    struct HeightRanker;
+
+    impl Ranker for HeightRanker {
+        fn score(&self, candidate: &SuggestionProfile, _context: &RankingContext) -> f64 {
+            candidate.height.unwrap_or(0) as f64 / 100.0
+        }
+    }
+
+    fn candidate(sort_hash: &str, height: i32) -> SuggestionProfile {
+        SuggestionProfile {
+            user_id: sort_hash.to_string(),
+            sort_hash: sort_hash.to_string(),
+            height: Some(height),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ranks_by_score_then_breaks_ties_with_the_sort_hash() {
+        let preferences = Preferences::default();
+        let context = RankingContext {
+            viewer_id: &Uuid::nil(),
+            preferences: &preferences,
+            now: Utc::now(),
+        };
+        let candidates = vec![
+            candidate("b", 50),
+            candidate("d", 10),
+            candidate("a", 50),
+            candidate("c", 90),
+        ];
+
+        let ranked: Vec<(String, f64)> = HeightRanker
+            .rank(candidates, &context)
+            .into_iter()
+            .map(|c| (c.profile.sort_hash, c.score))
+            .collect();
+
+        assert_eq!(
+            ranked,
+            vec![
+                ("c".to_string(), 0.9),
+                ("a".to_string(), 0.5),
+                ("b".to_string(), 0.5),
+                ("d".to_string(), 0.1),
+            ]
+        );
+    }
+}
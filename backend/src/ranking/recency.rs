@@ -0,0 +1,16 @@
+use crate::models::outputs::SuggestionProfile;
+use crate::ranking::{Ranker, RankingContext};
+
+/// Recently active users first: 1 when active right now, 0.5 a day ago, 0.25 three days ago...
+pub struct RecencyRanker;
+
+impl Ranker for RecencyRanker {
+    fn score(&self, candidate: &SuggestionProfile, context: &RankingContext) -> f64 {
+        let Some(last_active) = candidate.last_active else {
+            return 0.0;
+        };
+
+        let days = (context.now - last_active).num_seconds().max(0) as f64 / 86_400.0;
+        1.0 / (1.0 + days)
+    }
+}
@@ -0,0 +1,116 @@
+use crate::models::outputs::SuggestionProfile;
+use crate::ranking::{by_name, Ranker, RankingContext};
+
+/// Weighted average of other rankers
+pub struct WeightedRanker {
+    parts: Vec<(Box<dyn Ranker>, f64)>,
+}
+
+impl WeightedRanker {
+    pub fn new(parts: Vec<(Box<dyn Ranker>, f64)>) -> Self {
+        Self { parts }
+    }
+
+    /// Parse "recency=0.6,overlap=0.3,random=0.1"
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (name, weight) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("Expected name=weight, got '{}'", entry))?;
+            let ranker = by_name(name.trim()).ok_or_else(|| format!("Unknown ranker '{}'", name))?;
+            let weight: f64 = weight
+                .trim()
+                .parse()
+                .ok()
+                .filter(|w: &f64| w.is_finite() && *w >= 0.0)
+                .ok_or_else(|| format!("Invalid weight for '{}'", name))?;
+            parts.push((ranker, weight));
+        }
+
+        if parts.iter().all(|(_, weight)| *weight == 0.0) {
+            return Err("At least one weight must be positive".to_string());
+        }
+        Ok(Self::new(parts))
+    }
+
+    /// Weights from FEED_RANKER_WEIGHTS, or the default mix
+    pub fn from_env() -> Self {
+        match std::env::var("FEED_RANKER_WEIGHTS") {
+            Ok(spec) => Self::from_spec(&spec).expect("Invalid FEED_RANKER_WEIGHTS"),
+            Err(_) => Self::default(),
+        }
+    }
+}
+
+impl Default for WeightedRanker {
+    /// Mostly activity, then preferences, with a little shuffle
+    fn default() -> Self {
+        Self::from_spec("recency=0.6,overlap=0.3,random=0.1").expect("valid default weights")
+    }
+}
+
+impl Ranker for WeightedRanker {
+    fn score(&self, candidate: &SuggestionProfile, context: &RankingContext) -> f64 {
+        let total: f64 = self.parts.iter().map(|(_, weight)| weight).sum();
+        self.parts
+            .iter()
+            .map(|(ranker, weight)| weight * ranker.score(candidate, context))
+            .sum::<f64>()
+            / total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::inputs::Preferences;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    #[test]
+    fn parses_a_spec_with_spaces_and_a_trailing_comma() {
+        let ranker = WeightedRanker::from_spec(" recency=0.6, overlap = 0.3,random=0.1,").unwrap();
+        let weights: Vec<f64> = ranker.parts.iter().map(|(_, weight)| *weight).collect();
+        assert_eq!(weights, vec![0.6, 0.3, 0.1]);
+    }
+
+    #[test]
+    fn the_default_mix_is_valid() {
+        assert_eq!(WeightedRanker::default().parts.len(), 3);
+    }
+
+    #[test]
+    fn rejects_malformed_specs() {
+        for spec in [
+            "",
+            "recency",
+            "fast=1",
+            "recency=abc",
+            "recency=-1",
+            "recency=NaN",
+            "recency=inf",
+            "recency=0,random=0",
+        ] {
+            assert!(WeightedRanker::from_spec(spec).is_err(), "accepted {:?}", spec);
+        }
+    }
+
+    #[test]
+    fn scores_the_weighted_average() {
+        let ranker = WeightedRanker::from_spec("recency=1,random=3").unwrap();
+        // never active: recency 0, highest possible hash: random 1
+        let candidate = SuggestionProfile {
+            sort_hash: "ffffffff".to_string(),
+            ..Default::default()
+        };
+        let preferences = Preferences::default();
+        let context = RankingContext {
+            viewer_id: &Uuid::nil(),
+            preferences: &preferences,
+            now: Utc::now(),
+        };
+
+        assert_eq!(ranker.score(&candidate, &context), 0.75);
+    }
+}
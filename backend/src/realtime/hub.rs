@@ -0,0 +1,46 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedReceiver;
+use uuid::Uuid;
+
+use crate::models::outputs::{Message, UserSummary};
+
+/// Events pushed to connected clients over the WebSocket
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RealtimeEvent {
+    /// A new message in one of the recipient's matches
+    Message { match_id: String, message: Message },
+    /// A new match was created with `with_user`
+    Match { match_id: String, with_user: UserSummary },
+    /// The other participant unmatched; the conversation is gone
+    Unmatch { match_id: String },
+    /// Someone liked the recipient
+    Like { from_user_id: String },
+    /// `reader_id` read the messages of a match up to `up_to_message_id`
+    Read {
+        match_id: String,
+        reader_id: String,
+        up_to_message_id: String,
+    },
+    /// A request sent over the socket failed (only sent to that socket)
+    Error { message: String },
+}
+
+/// Stream of events for one connection
+pub type Subscription = UnboundedReceiver<RealtimeEvent>;
+
+/// Fan-out hub delivering events to every live connection of a user.
+/// Implementations decide how far an event travels (this process only, or all nodes)
+#[async_trait]
+pub trait EventHub: Send + Sync {
+    /// Deliver an event to every connection of `recipient`
+    async fn publish(&self, recipient: Uuid, event: RealtimeEvent);
+
+    /// Register a new connection for `user_id`
+    fn subscribe(&self, user_id: Uuid) -> Subscription;
+
+    /// Forget the connections of `user_id` whose receiver has been dropped.
+    /// Call it when a connection closes, otherwise they linger until the next event for that user
+    fn unsubscribe(&self, user_id: Uuid);
+}
@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+use uuid::Uuid;
+
+use super::hub::{EventHub, RealtimeEvent, Subscription};
+
+/// In-process hub: only reaches connections held by this backend instance
+#[derive(Default)]
+pub struct InMemoryHub {
+    subscribers: Mutex<HashMap<Uuid, Vec<UnboundedSender<RealtimeEvent>>>>,
+}
+
+impl InMemoryHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send an event to the local connections of `recipient`, pruning closed ones
+    pub fn dispatch(&self, recipient: Uuid, event: RealtimeEvent) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+
+        let Some(senders) = subscribers.get_mut(&recipient) else {
+            return;
+        };
+
+        senders.retain(|sender| sender.send(event.clone()).is_ok());
+
+        if senders.is_empty() {
+            subscribers.remove(&recipient);
+        }
+    }
+}
+
+#[async_trait]
+impl EventHub for InMemoryHub {
+    async fn publish(&self, recipient: Uuid, event: RealtimeEvent) {
+        self.dispatch(recipient, event);
+    }
+
+    fn subscribe(&self, user_id: Uuid) -> Subscription {
+        let (sender, receiver) = unbounded_channel();
+
+        self.subscribers
+            .lock()
+            .unwrap()
+            .entry(user_id)
+            .or_default()
+            .push(sender);
+
+        receiver
+    }
+
+    fn unsubscribe(&self, user_id: Uuid) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+
+        if let Some(senders) = subscribers.get_mut(&user_id) {
+            senders.retain(|sender| !sender.is_closed());
+            if senders.is_empty() {
+                subscribers.remove(&user_id);
+            }
+        }
+    }
+}
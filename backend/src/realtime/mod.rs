@@ -0,0 +1,7 @@
+pub mod hub;
+pub mod memory;
+pub mod postgres;
+
+pub use hub::{EventHub, RealtimeEvent, Subscription};
+pub use memory::InMemoryHub;
+pub use postgres::PgNotifyHub;
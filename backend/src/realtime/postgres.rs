@@ -0,0 +1,114 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use sqlx::postgres::PgListener;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::hub::{EventHub, RealtimeEvent, Subscription};
+use super::memory::InMemoryHub;
+
+/// Postgres channel every backend instance listens on
+const CHANNEL: &str = "realtime_events";
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    recipient: Uuid,
+    event: RealtimeEvent,
+}
+
+/// Multi-node hub: events go through Postgres LISTEN/NOTIFY so every backend
+/// instance receives them and delivers to the connections it holds
+pub struct PgNotifyHub {
+    pool: PgPool,
+    local: Arc<InMemoryHub>,
+}
+
+/// Wait after a listener failure, doubled on each failure in a row
+const RETRY_MIN: Duration = Duration::from_secs(1);
+const RETRY_MAX: Duration = Duration::from_secs(30);
+
+/// A new connection listening on the channel
+async fn listen(pool: &PgPool) -> Result<PgListener, sqlx::Error> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(CHANNEL).await?;
+    Ok(listener)
+}
+
+impl PgNotifyHub {
+    /// Start listening on the channel and spawn the task dispatching notifications
+    pub async fn start(pool: PgPool) -> Result<Self, sqlx::Error> {
+        let mut listener = listen(&pool).await?;
+
+        let local = Arc::new(InMemoryHub::new());
+        let dispatcher = Arc::clone(&local);
+        let listener_pool = pool.clone();
+
+        actix_web::rt::spawn(async move {
+            let mut retry_in = RETRY_MIN;
+            loop {
+                // recv() reconnects by itself after a lost connection, an error means
+                // that failed too: back off, then start over with a new LISTEN.
+                // Notifications sent in the meantime are lost
+                let notification = match listener.recv().await {
+                    Ok(n) => n,
+                    Err(e) => {
+                        eprintln!("Realtime listener error, retrying in {:?}: {:?}", retry_in, e);
+                        actix_web::rt::time::sleep(retry_in).await;
+                        retry_in = (retry_in * 2).min(RETRY_MAX);
+
+                        match listen(&listener_pool).await {
+                            Ok(new_listener) => listener = new_listener,
+                            Err(e) => eprintln!("Realtime listener reconnect failed: {:?}", e),
+                        }
+                        continue;
+                    }
+                };
+                retry_in = RETRY_MIN;
+
+                match serde_json::from_str::<Envelope>(notification.payload()) {
+                    Ok(envelope) => dispatcher.dispatch(envelope.recipient, envelope.event),
+                    Err(e) => eprintln!("Invalid realtime notification: {:?}", e),
+                }
+            }
+        });
+
+        Ok(Self { pool, local })
+    }
+}
+
+#[async_trait]
+impl EventHub for PgNotifyHub {
+    async fn publish(&self, recipient: Uuid, event: RealtimeEvent) {
+        let envelope = Envelope { recipient, event };
+
+        let payload = match serde_json::to_string(&envelope) {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("Failed to serialize realtime event: {:?}", e);
+                return;
+            }
+        };
+
+        let result = sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(CHANNEL)
+            .bind(&payload)
+            .execute(&self.pool)
+            .await;
+
+        // NOTIFY can fail (e.g. payload over 8000 bytes) - at least reach local connections
+        if let Err(e) = result {
+            eprintln!("Failed to notify realtime event: {:?}", e);
+            self.local.dispatch(envelope.recipient, envelope.event);
+        }
+    }
+
+    fn subscribe(&self, user_id: Uuid) -> Subscription {
+        self.local.subscribe(user_id)
+    }
+
+    fn unsubscribe(&self, user_id: Uuid) {
+        self.local.unsubscribe(user_id)
+    }
+}
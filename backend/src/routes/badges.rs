@@ -0,0 +1,20 @@
+use actix_web::{HttpResponse, web};
+use sqlx::PgPool;
+
+use crate::db::{interact_queries, match_queries};
+use crate::error::ApiError;
+use crate::firebaseauth::CurrentUser;
+use crate::models::outputs::BadgesResponse;
+
+/// GET /badges - Counters for the app's tab badges (cheap enough to poll)
+pub async fn get_badges(pool: web::Data<PgPool>, user: CurrentUser) -> Result<HttpResponse, ApiError> {
+    let (unread_messages, unread_conversations) =
+        match_queries::count_unread_for_user(&pool, &user.id).await?;
+    let pending_likes = interact_queries::count_pending_likes(&pool, &user.id).await?;
+
+    Ok(HttpResponse::Ok().json(BadgesResponse {
+        unread_messages,
+        unread_conversations,
+        pending_likes,
+    }))
+}
@@ -1,82 +1,163 @@
-use actix_web::{HttpMessage, HttpRequest, HttpResponse, Responder, web};
+use actix_web::{HttpResponse, web};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use uuid::Uuid;
 
-use crate::db::{profile_queries, user_queries};
+use crate::db::profile_queries::{SessionPage, Suggestions};
+use crate::db::feed_queries::FeedSession;
+use crate::db::{feed_queries, profile_queries, user_queries};
+use crate::error::ApiError;
+use crate::firebaseauth::CurrentUser;
 use crate::models::inputs::Preferences;
-use crate::models::outputs::{FeedResponse, ProfileDetails, StatusResponse, UserProfile};
+use crate::models::outputs::{Distance, FeedResponse, ProfileDetails, UserProfile};
+use crate::models::state::FeedConfig;
+use crate::ranking::{Ranker, RankingContext};
 
-use firebase_auth::FirebaseUser;
+#[derive(Deserialize)]
+pub struct FeedQuery {
+    // Also bring back profiles passed a while ago (see FeedConfig::second_look_days).
+    // Picked when a session starts, ignored with a cursor
+    pub second_look: Option<bool>,
+    // next_cursor of the previous page; without it a new feed session starts
+    pub cursor: Option<String>,
+}
 
-pub async fn get_feed(pool: web::Data<PgPool>, req: HttpRequest) -> impl Responder {
-    println!("GET /feed invoked");
+const FEED_PAGE_SIZE: usize = 20;
 
-    let user: FirebaseUser = match req.extensions().get::<FirebaseUser>().cloned() {
-        Some(user) => user,
-        None => {
-            return HttpResponse::Unauthorized().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some("Unauthorized".to_string()),
-            });
-        }
-    };
+/// What next_cursor carries: the session and the last profile already returned.
+/// The rest of the session is stored with it (feed_queries::FeedSession)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct FeedCursor {
+    session_id: Uuid,
+    after: Option<FeedPosition>,
+}
 
-    println!("User details {:?}", user.email);
-    // println!("User details {:?}", user.phone); // TODO: Add phone support
+/// A place in the ranked order: score descending, then sort_hash ascending
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct FeedPosition {
+    score: f64,
+    sort_hash: String,
+}
 
-    // Find user by email or phone
-    let (user_id, preferences_opt) = match user_queries::get_user_with_preferences_by_identifier(
-        &pool,
-        user.email.as_deref(),
-        None,
-    )
-    .await
-    {
-        Ok(Some((uid, prefs))) => (uid, prefs),
-        Ok(None) => {
-            return HttpResponse::NotFound().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some("User not found".to_string()),
-            });
-        }
-        Err(e) => {
-            return HttpResponse::InternalServerError().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some(format!("Failed to find user: {}", e)),
-            });
-        }
-    };
+/// A new session for today: the seed rotates daily, so the order is shuffled once a day
+fn new_session(user_id: &Uuid, second_look_days: Option<i32>) -> FeedSession {
+    let started_at = Utc::now();
+    FeedSession {
+        id: Uuid::new_v4(),
+        seed: format!("{}:{}", user_id, started_at.date_naive()),
+        started_at,
+        second_look_days,
+    }
+}
+
+fn encode_cursor(cursor: &FeedCursor) -> Option<String> {
+    serde_json::to_vec(cursor)
+        .ok()
+        .map(|json| URL_SAFE_NO_PAD.encode(json))
+}
+
+fn decode_cursor(cursor: &str) -> Option<FeedCursor> {
+    let json = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
+pub async fn get_feed(
+    pool: web::Data<PgPool>,
+    user: CurrentUser,
+    query: web::Query<FeedQuery>,
+    feed_config: web::Data<FeedConfig>,
+    ranker: web::Data<dyn Ranker>,
+) -> Result<HttpResponse, ApiError> {
+    println!("GET /feed invoked");
+
+    let user_id = user.id;
 
     // Check if preferences exist
-    let Some(prefs_json) = preferences_opt else {
-        return HttpResponse::BadRequest().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some("User has no preferences set".to_string()),
-        });
+    let Some(prefs_json) = user_queries::get_user_preferences(&pool, &user_id).await? else {
+        return Err(ApiError::bad_request("User has no preferences set"));
     };
 
     // Parse the JSON into Preferences struct
-    let preference: Preferences = match serde_json::from_value(prefs_json) {
-        Ok(p) => p,
-        Err(e) => {
-            return HttpResponse::InternalServerError().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some(format!("Failed to parse preferences: {}", e)),
-            });
+    let preference: Preferences = serde_json::from_value(prefs_json)
+        .map_err(|e| anyhow::Error::new(e).context("Failed to parse stored preferences"))?;
+
+    let distance_unit = feed_config.distance_unit;
+
+    // Continue the session of the cursor, or start a new one
+    let (session, after) = match &query.cursor {
+        Some(cursor) => {
+            let cursor = decode_cursor(cursor).ok_or_else(|| ApiError::bad_request("Invalid cursor"))?;
+            let session = feed_queries::get_session(&pool, &user_id, &cursor.session_id)
+                .await?
+                .ok_or_else(|| ApiError::bad_request("Feed session expired, start again without a cursor"))?;
+            (session, cursor.after)
+        }
+        None => {
+            let second_look_days = query
+                .second_look
+                .unwrap_or(false)
+                .then_some(feed_config.second_look_days);
+            let session = new_session(&user_id, second_look_days);
+
+            // Rank the candidates now, the pages then walk this fixed order
+            let candidates = profile_queries::get_suggestions(
+                &pool,
+                &user_id,
+                &preference,
+                session.second_look_days,
+                distance_unit,
+                &session.seed,
+                &Suggestions::Pool(feed_config.session_pool_size),
+            )
+            .await?;
+
+            let context = RankingContext {
+                viewer_id: &user_id,
+                preferences: &preference,
+                now: session.started_at,
+            };
+            let ranked = ranker.rank(candidates, &context);
+            let entries: Vec<(&str, f64, &str)> = ranked
+                .iter()
+                .map(|c| (c.profile.user_id.as_str(), c.score, c.profile.sort_hash.as_str()))
+                .collect();
+            feed_queries::start_session(&pool, &user_id, &session, &entries).await?;
+
+            (session, None)
         }
     };
 
-    // Get suggestions based on gender preference only (for now)
-    let suggestions =
-        match profile_queries::get_suggestions(&pool, preference.gender_preference, &user_id).await
-        {
-            Ok(profiles) => profiles,
-            Err(e) => {
-                return HttpResponse::InternalServerError().json(StatusResponse {
-                    status: "error".to_string(),
-                    message: Some(format!("Failed to get suggestions: {}", e)),
-                });
-            }
-        };
+    let page = Suggestions::Page(SessionPage {
+        session_id: &session.id,
+        after: after.as_ref().map(|after| (after.score, after.sort_hash.as_str())),
+        limit: FEED_PAGE_SIZE as i64 + 1,
+    });
+    let mut suggestions = profile_queries::get_suggestions(
+        &pool,
+        &user_id,
+        &preference,
+        session.second_look_days,
+        distance_unit,
+        &session.seed,
+        &page,
+    )
+    .await?;
+    let has_more = suggestions.len() > FEED_PAGE_SIZE;
+    suggestions.truncate(FEED_PAGE_SIZE);
+
+    let next_cursor = match suggestions.last() {
+        Some(last) if has_more => encode_cursor(&FeedCursor {
+            session_id: session.id,
+            after: last.session_score.map(|score| FeedPosition {
+                score,
+                sort_hash: last.sort_hash.clone(),
+            }),
+        }),
+        _ => None,
+    };
 
     // Convert SuggestionProfile to UserProfile for the response
     let profiles: Vec<UserProfile> = suggestions
@@ -84,7 +165,7 @@ pub async fn get_feed(pool: web::Data<PgPool>, req: HttpRequest) -> impl Respond
         .map(|p| UserProfile {
             id: p.user_id.clone(),
             images: p.images.and_then(|json| serde_json::from_value(json).ok()),
-            prompts: None,
+            prompts: p.prompts.and_then(|json| serde_json::from_value(json).ok()),
             details: Some(ProfileDetails {
                 name: p.name,
                 bio: p.bio,
@@ -93,7 +174,7 @@ pub async fn get_feed(pool: web::Data<PgPool>, req: HttpRequest) -> impl Respond
                 gender: p.gender,
                 sexuality: p.sexuality,
                 height: p.height,
-                location: p.location,
+                location: None, // only the distance is shared
                 job: p.job,
                 company: p.company,
                 school: p.school,
@@ -105,10 +186,81 @@ pub async fn get_feed(pool: web::Data<PgPool>, req: HttpRequest) -> impl Respond
                 drinks: p.drinks,
                 smokes: p.smokes,
             }),
+            distance: p.distance.map(|value| Distance {
+                value,
+                unit: distance_unit.as_str().to_string(),
+            }),
         })
         .collect();
 
-    println!("Profiles {:?}", profiles);
+    Ok(HttpResponse::Ok().json(FeedResponse {
+        profiles,
+        next_cursor,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    HttpResponse::Ok().json(FeedResponse { profiles })
+    #[test]
+    fn cursor_round_trips() {
+        let cursor = FeedCursor {
+            session_id: Uuid::new_v4(),
+            after: Some(FeedPosition {
+                score: 0.1 + 0.2,
+                sort_hash: "0f343b0931126a20f133d67c2b018a3b".to_string(),
+            }),
+        };
+
+        let decoded = decode_cursor(&encode_cursor(&cursor).unwrap()).unwrap();
+
+        assert_eq!(decoded.session_id, cursor.session_id);
+        // the score is compared for equality in SQL, it has to come back bit for bit
+        assert_eq!(decoded.after.as_ref().unwrap().score.to_bits(), (0.1f64 + 0.2).to_bits());
+        assert_eq!(decoded.after, cursor.after);
+    }
+
+    #[test]
+    fn cursor_of_a_first_page_has_no_position() {
+        let cursor = FeedCursor {
+            session_id: Uuid::new_v4(),
+            after: None,
+        };
+
+        let decoded = decode_cursor(&encode_cursor(&cursor).unwrap()).unwrap();
+
+        assert_eq!(decoded.after, None);
+    }
+
+    #[test]
+    fn sessions_of_a_day_share_the_seed() {
+        let user_id = Uuid::new_v4();
+
+        let first = new_session(&user_id, None);
+        let second = new_session(&user_id, Some(30));
+
+        assert_ne!(first.id, second.id);
+        assert_eq!(first.seed, second.seed);
+        assert_ne!(first.seed, new_session(&Uuid::new_v4(), None).seed);
+    }
+
+    #[test]
+    fn rejects_malformed_cursors() {
+        let without_id = URL_SAFE_NO_PAD.encode(r#"{"after":null}"#);
+        let bad_score = URL_SAFE_NO_PAD.encode(format!(
+            r#"{{"session_id":"{}","after":{{"score":"high","sort_hash":"a"}}}}"#,
+            Uuid::nil()
+        ));
+
+        for cursor in [
+            "",
+            "not a cursor!",
+            &URL_SAFE_NO_PAD.encode("not json"),
+            &without_id,
+            &bad_score,
+        ] {
+            assert!(decode_cursor(cursor).is_none(), "accepted {:?}", cursor);
+        }
+    }
 }
@@ -0,0 +1,48 @@
+use actix_web::http::header;
+use actix_web::{HttpRequest, HttpResponse, web};
+
+use crate::error::ApiError;
+use crate::file_storage::{LocalStore, ObjectStore};
+
+/// PUT /files/{token} - Upload target of the local store's presigned URLs.
+/// Like S3, the Content-Type has to match the one the URL was issued for
+pub async fn upload(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Bytes,
+    store: web::Data<LocalStore>,
+) -> Result<HttpResponse, ApiError> {
+    let grant = store
+        .verify(&path, "PUT")
+        .ok_or_else(|| ApiError::forbidden("Invalid or expired upload URL"))?;
+
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    if grant.content_type.as_deref() != Some(content_type) {
+        return Err(ApiError::forbidden("Content-Type does not match the upload URL"));
+    }
+
+    store.put(&grant.key, body, content_type).await?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// GET /files/{token} - Download target of the local store's presigned URLs
+pub async fn download(path: web::Path<String>, store: web::Data<LocalStore>) -> Result<HttpResponse, ApiError> {
+    let grant = store
+        .verify(&path, "GET")
+        .ok_or_else(|| ApiError::forbidden("Invalid or expired download URL"))?;
+
+    let object = store
+        .get(&grant.key)
+        .await?
+        .ok_or_else(|| ApiError::not_found("File not found"))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(object.info.content_type.unwrap_or_else(|| "application/octet-stream".to_string()))
+        .body(object.body))
+}
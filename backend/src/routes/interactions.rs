@@ -1,62 +1,184 @@
-use crate::db::interact_queries;
-use crate::models::inputs::InteractRequest;
-use crate::models::outputs::StatusResponse;
-use actix_web::{HttpMessage, HttpRequest, HttpResponse, Responder, web};
+use crate::db::{interact_queries, safety_queries, user_queries};
+use crate::error::ApiError;
+use crate::firebaseauth::CurrentUser;
+use crate::models::inputs::{InteractAction, InteractRequest};
+use crate::file_storage::FileService;
+use crate::models::outputs::{LikeCard, LikedContent, LikerProfile, LikesResponse, MatchData, MatchResponse};
+use crate::realtime::{EventHub, RealtimeEvent};
+use actix_web::{HttpResponse, web};
 use sqlx::PgPool;
 use uuid::Uuid;
 use serde::{Deserialize};
 
-use firebase_auth::FirebaseUser;
+const MAX_COMMENT_LENGTH: usize = 500;
+const DEFAULT_LIKES_LIMIT: i64 = 20;
+const MAX_LIKES_LIMIT: i64 = 50;
 
-pub async fn interact(body: web::Json<InteractRequest>, pool: web::Data<PgPool>, req: HttpRequest) -> impl Responder {
+pub async fn interact(
+    body: web::Json<InteractRequest>,
+    pool: web::Data<PgPool>,
+    hub: web::Data<dyn EventHub>,
+    user: CurrentUser,
+) -> Result<HttpResponse, ApiError> {
     println!("invoked /interactions");
-    
-    // getting the userid 
-    let user: FirebaseUser = match req.extensions().get::<FirebaseUser>().cloned() {
-        Some(user) => user,
-        // NOT POSSIBLE AS IS IS PASSED FROM THE MIDDLEWARE
-        None => return HttpResponse::Unauthorized().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some("Unauthorized".to_string()),
-        })
-    };
 
-    // Get user ID from database using Firebase email
-    let user_id = match crate::db::user_queries::get_user_id_by_email(&pool, user.email.as_deref()).await {
-        Ok(Some(id)) => id,
-        Ok(None) => return HttpResponse::NotFound().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some("User not found".to_string()),
-        }),
-        Err(e) => return HttpResponse::InternalServerError().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some(format!("Failed to get user: {}", e)),
-        })
+    let user_id = user.id;
+
+    let mut body = body.into_inner();
+
+    let target_user_id = Uuid::parse_str(&body.target_user_id)
+        .map_err(|_| ApiError::invalid("target_user_id", "Invalid target user ID"))?;
+
+    if target_user_id == user_id {
+        return Err(ApiError::invalid("target_user_id", "You cannot interact with yourself"));
+    }
+
+    // Validate input
+    body.comment = body
+        .comment
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .map(String::from);
+
+    if let Some(comment) = &body.comment {
+        if body.action != InteractAction::Like {
+            return Err(ApiError::invalid("comment", "Comments can only be sent with a like"));
+        }
+
+        if comment.chars().count() > MAX_COMMENT_LENGTH {
+            return Err(ApiError::invalid(
+                "comment",
+                format!("Comment cannot exceed {} characters", MAX_COMMENT_LENGTH),
+            ));
+        }
+    }
+
+    // Blocked users don't exist for each other
+    if safety_queries::is_blocked_between(&pool, &user_id, &target_user_id).await? {
+        return Err(ApiError::not_found("User not found"));
+    }
+
+    // The liked image / prompt must be one of the target's
+    if let Some(context) = &body.context {
+        let context_id = Uuid::parse_str(&context.id)
+            .map_err(|_| ApiError::invalid("context.id", "Invalid context ID"))?;
+
+        if !interact_queries::context_belongs_to(&pool, context.r#type, &context_id, &target_user_id).await? {
+            return Err(ApiError::invalid("context.id", "Liked content not found on this profile"));
+        }
+    }
+
+    let is_like = body.action == InteractAction::Like;
+
+    let match_id = interact_queries::interact(&pool, &user_id, &target_user_id, &body)
+        .await
+        .map_err(ApiError::user_not_found)?;
+
+    let Some(match_id) = match_id else {
+        if is_like {
+            hub.publish(target_user_id, RealtimeEvent::Like {
+                from_user_id: user_id.to_string(),
+            })
+            .await;
+        }
+
+        return Ok(HttpResponse::Ok().json(MatchResponse {
+            status: "SENT".to_string(),
+            match_id: None,
+            match_data: None,
+        }))
     };
 
-    let Ok(target_user_id) = Uuid::parse_str(&body.target_user_id) else {
-        return HttpResponse::BadRequest().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some("Invalid target user ID".to_string()),
-        })
+    // It's a match - attach the other user's summary
+    let match_data = match user_queries::get_user_summary(&pool, &target_user_id).await {
+        Ok(summary) => summary.map(|user| MatchData { user }),
+        Err(e) => {
+            eprintln!("Failed to load matched user summary: {:?}", e);
+            None
+        }
     };
 
-    match interact_queries::interact(&pool, &user_id, &target_user_id, &body.into_inner()).await {
-        Ok(_) => HttpResponse::Ok().json(StatusResponse {
-            status: "success".to_string(),
-            message: Some("Interaction recorded".to_string()),
-        }),
-        Err(_) => HttpResponse::InternalServerError().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some("Failed to record interaction".to_string()),
-        })
+    // Let the other side know right away
+    match user_queries::get_user_summary(&pool, &user_id).await {
+        Ok(Some(me)) => {
+            hub.publish(target_user_id, RealtimeEvent::Match {
+                match_id: match_id.to_string(),
+                with_user: me,
+            })
+            .await
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("Failed to load user summary for match event: {:?}", e),
     }
-}
 
+    Ok(HttpResponse::Ok().json(MatchResponse {
+        status: "MATCH".to_string(),
+        match_id: Some(match_id.to_string()),
+        match_data,
+    }))
+}
 
 #[derive(Deserialize)]
-pub struct PathParams {
-    pub user_id: Uuid,
+pub struct LikesQuery {
+    pub cursor: Option<Uuid>, // id of the last like from the previous page
+    pub limit: Option<i64>,
+}
+
+/// GET /likes - The caller's "Likes You" inbox, most recent first
+pub async fn get_likes(
+    pool: web::Data<PgPool>,
+    user: CurrentUser,
+    query: web::Query<LikesQuery>,
+    file_service: web::Data<FileService>,
+) -> Result<HttpResponse, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIKES_LIMIT).clamp(1, MAX_LIKES_LIMIT);
+
+    let rows = interact_queries::get_likes_inbox(&pool, &user.id, query.cursor, limit).await?;
+
+    let mut likes: Vec<LikeCard> = Vec::with_capacity(rows.len());
+    for row in rows {
+        let photo_url = match row.photo_key {
+            Some(key) => Some(resolve_url(&file_service, key).await),
+            None => None,
+        };
+
+        let liked_content = match (row.image_id, row.image_key, row.prompt_id) {
+            (Some(id), Some(key), _) => Some(LikedContent::Image {
+                id: id.to_string(),
+                url: resolve_url(&file_service, key).await,
+            }),
+            (_, _, Some(id)) => Some(LikedContent::Prompt {
+                id: id.to_string(),
+                question: row.prompt_question.unwrap_or_default(),
+                answer: row.prompt_answer.unwrap_or_default(),
+            }),
+            _ => None,
+        };
+
+        likes.push(LikeCard {
+            id: row.id.to_string(),
+            user: LikerProfile {
+                id: row.from_user_id.to_string(),
+                name: row.name,
+                birthdate: row.birthdate,
+                photo_url,
+            },
+            liked_content,
+            comment: row.comment,
+            created_at: row.created_at.to_rfc3339(),
+        });
+    }
+
+    Ok(HttpResponse::Ok().json(LikesResponse { likes }))
+}
+
+/// Resolve a stored image key into a viewable URL, falling back to the key
+async fn resolve_url(file_service: &FileService, key: String) -> String {
+    match file_service.download_file(&key).await {
+        Ok(response) => response.download_url,
+        Err(_) => key,
+    }
 }
 
 #[derive(Deserialize)]
@@ -64,28 +186,9 @@ pub struct QueryParams {
     pub action: String,
 }
 
-// GET ALL THE INTEACTIONS WHICH ARE TO ME
-pub async fn get_interactions_for_me(pool: web::Data<PgPool>, params: web::Path<PathParams>, query: web::Query<QueryParams>) -> impl Responder {
-    let interactions = match interact_queries::get_interactions_to_user_id(&pool, &params.user_id, &query.action).await {
-        Ok(interactions) => interactions,
-        Err(e) => return HttpResponse::InternalServerError().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some(format!("Failed to get interactions: {}", e)),
-        })
-    };
+// GET ALL THE INTEACTIONS OF THE CALLER TO EVERYONE
+pub async fn get_interactions_to_everyone(pool: web::Data<PgPool>, user: CurrentUser, query: web::Query<QueryParams>) -> Result<HttpResponse, ApiError> {
+    let interactions = interact_queries::get_interactions_from_user_id(&pool, &user.id, &query.action).await?;
 
-    HttpResponse::Ok().json(interactions)
+    Ok(HttpResponse::Ok().json(interactions))
 }
-
-// GET ALL THE INTEACTIONS WHICH ARE TO EVERYONE
-pub async fn get_interactions_to_everyone(pool: web::Data<PgPool>, params: web::Path<PathParams>, query: web::Query<QueryParams>) -> impl Responder {
-    let interactions = match interact_queries::get_interactions_from_user_id(&pool, &params.user_id, &query.action).await {
-        Ok(interactions) => interactions,
-        Err(e) => return HttpResponse::InternalServerError().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some(format!("Failed to get interactions: {}", e)),
-        })
-    };
-
-    HttpResponse::Ok().json(interactions)
-}
\ No newline at end of file
@@ -1,23 +1,320 @@
-use crate::models::inputs::SendMessageRequest;
-use actix_web::{HttpResponse, Responder, web};
+use crate::db::match_queries::MessageRow;
+use crate::db::match_queries;
+use crate::error::ApiError;
+use crate::file_storage::FileService;
+use crate::firebaseauth::CurrentUser;
+use crate::models::inputs::{MarkReadRequest, SendMessageRequest, UnmatchRequest};
+use crate::realtime::{EventHub, RealtimeEvent};
+use crate::models::outputs::{
+    MarkReadResponse, MatchSummary, MatchesResponse, Message, MessageHistoryResponse,
+    MessagePreview, StatusResponse, UserSummary,
+};
+use actix_web::{HttpResponse, web};
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
 
+const DEFAULT_MATCHES_LIMIT: i64 = 20;
+const MAX_MATCHES_LIMIT: i64 = 50;
+const DEFAULT_MESSAGES_LIMIT: i64 = 50;
+const MAX_MESSAGES_LIMIT: i64 = 100;
+pub const MAX_MESSAGE_LENGTH: usize = 2000;
+const MAX_UNMATCH_REASON_LENGTH: usize = 500;
 
+#[derive(Deserialize)]
+pub struct MatchesQuery {
+    pub cursor: Option<Uuid>, // id of the last match from the previous page
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct MessagesQuery {
+    pub before: Option<Uuid>, // message id - return messages older than it
+    pub after: Option<Uuid>,  // message id - return messages newer than it
+    pub limit: Option<i64>,
+}
+
+/// GET /matches - List the caller's matches, most recent activity first
+pub async fn get_matches(
+    pool: web::Data<PgPool>,
+    user: CurrentUser,
+    query: web::Query<MatchesQuery>,
+    file_service: web::Data<FileService>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = user.id;
+
+    let limit = query.limit.unwrap_or(DEFAULT_MATCHES_LIMIT).clamp(1, MAX_MATCHES_LIMIT);
+
+    let rows = match_queries::get_matches_for_user(&pool, &user_id, query.cursor, limit).await?;
+
+    let mut matches: Vec<MatchSummary> = Vec::with_capacity(rows.len());
+    for row in rows {
+        // Resolve the stored key of the first photo into a viewable URL
+        let photo_url = match row.other_photo_key {
+            Some(key) => match file_service.download_file(&key).await {
+                Ok(response) => Some(response.download_url),
+                Err(_) => Some(key),
+            },
+            None => None,
+        };
 
-pub async fn get_matches() -> impl Responder {
-    HttpResponse::Ok().body("Matches: Get All Matches")
+        let last_message = match (row.last_message, row.last_message_at) {
+            (Some(text), Some(created_at)) => Some(MessagePreview {
+                text,
+                created_at: created_at.to_rfc3339(),
+                is_read: row.last_message_is_read.unwrap_or(false),
+            }),
+            _ => None,
+        };
+
+        matches.push(MatchSummary {
+            id: row.match_id.to_string(),
+            with_user: UserSummary {
+                id: row.other_user_id.to_string(),
+                is_profile_complete: row.other_is_profile_complete,
+                is_new_user: row.other_is_new_user,
+            },
+            name: row.other_name,
+            photo_url,
+            last_message,
+            unread_count: row.unread_count,
+        });
+    }
+
+    let (total_unread, _) = match_queries::count_unread_for_user(&pool, &user_id).await?;
+
+    Ok(HttpResponse::Ok().json(MatchesResponse {
+        matches,
+        total_unread,
+    }))
 }
 
-pub async fn get_messages(path: web::Path<String>) -> impl Responder {
+/// GET /matches/{id}/messages - Get a page of the chat history of a match
+pub async fn get_messages(
+    pool: web::Data<PgPool>,
+    user: CurrentUser,
+    path: web::Path<Uuid>,
+    query: web::Query<MessagesQuery>,
+) -> Result<HttpResponse, ApiError> {
     let match_id = path.into_inner();
-    println!("Messages: Get history for match {}", match_id);
-    HttpResponse::Ok().body(format!("Messages: Get Chat History for {}", match_id))
+
+    let user_id = user.id;
+
+    if query.before.is_some() && query.after.is_some() {
+        return Err(ApiError::bad_request("Use either before or after, not both"));
+    }
+
+    if !match_queries::is_match_participant(&pool, &match_id, &user_id).await? {
+        return Err(ApiError::forbidden("Not a participant of this match"));
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_MESSAGES_LIMIT).clamp(1, MAX_MESSAGES_LIMIT);
+
+    let rows = match_queries::get_messages(&pool, &match_id, query.before, query.after, limit).await?;
+
+    Ok(HttpResponse::Ok().json(MessageHistoryResponse {
+        messages: rows.iter().map(to_message).collect(),
+    }))
 }
 
+/// POST /matches/{id}/messages - Send a message to a match
 pub async fn send_message(
-    path: web::Path<String>,
+    pool: web::Data<PgPool>,
+    hub: web::Data<dyn EventHub>,
+    user: CurrentUser,
+    path: web::Path<Uuid>,
     body: web::Json<SendMessageRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
+    let match_id = path.into_inner();
+
+    let user_id = user.id;
+
+    let row = deliver_message(&pool, hub.get_ref(), &match_id, &user_id, &body.text).await?;
+
+    Ok(HttpResponse::Created().json(to_message(&row)))
+}
+
+/// POST /matches/{id}/read - Mark the messages of a match as read up to a message
+pub async fn mark_read(
+    pool: web::Data<PgPool>,
+    hub: web::Data<dyn EventHub>,
+    user: CurrentUser,
+    path: web::Path<Uuid>,
+    body: web::Json<MarkReadRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let match_id = path.into_inner();
+
+    let user_id = user.id;
+
+    let message_id = Uuid::parse_str(&body.message_id)
+        .map_err(|_| ApiError::invalid("message_id", "Invalid message ID"))?;
+
+    let participants = match match_queries::get_match_participants(&pool, &match_id).await? {
+        Some((user1_id, user2_id)) if user_id == user1_id || user_id == user2_id => {
+            (user1_id, user2_id)
+        }
+        _ => return Err(ApiError::forbidden("Not a participant of this match")),
+    };
+
+    let marked = match_queries::mark_messages_read(&pool, &match_id, &user_id, &message_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Message not found in this match"))?;
+
+    // Send the receipt to the other participant if anything changed
+    if marked > 0 {
+        let other_id = if participants.0 == user_id { participants.1 } else { participants.0 };
+        hub.publish(other_id, RealtimeEvent::Read {
+            match_id: match_id.to_string(),
+            reader_id: user_id.to_string(),
+            up_to_message_id: message_id.to_string(),
+        })
+        .await;
+    }
+
+    Ok(HttpResponse::Ok().json(MarkReadResponse { marked }))
+}
+
+/// DELETE /matches/{id} - Unmatch: removes the match and its messages for both sides
+pub async fn unmatch(
+    pool: web::Data<PgPool>,
+    hub: web::Data<dyn EventHub>,
+    user: CurrentUser,
+    path: web::Path<Uuid>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
     let match_id = path.into_inner();
-    println!("Messages: Send to match {}: {}", match_id, body.text);
-    HttpResponse::Ok().body(format!("Messages: Send Message to {}", match_id))
+
+    let user_id = user.id;
+
+    // The body is optional, but one that is sent has to be valid
+    let body: Option<UnmatchRequest> = if body.trim_ascii().is_empty() {
+        None
+    } else {
+        Some(serde_json::from_slice(&body).map_err(|e| ApiError::bad_request(format!("Invalid body: {}", e)))?)
+    };
+
+    let reason = body
+        .as_ref()
+        .and_then(|b| b.reason.as_deref())
+        .map(str::trim)
+        .filter(|r| !r.is_empty());
+
+    if reason.is_some_and(|r| r.chars().count() > MAX_UNMATCH_REASON_LENGTH) {
+        return Err(ApiError::invalid(
+            "reason",
+            format!("Reason cannot exceed {} characters", MAX_UNMATCH_REASON_LENGTH),
+        ));
+    }
+
+    let other_user_id = match match_queries::get_match_participants(&pool, &match_id).await? {
+        Some((user1_id, user2_id)) if user_id == user1_id => user2_id,
+        Some((user1_id, user2_id)) if user_id == user2_id => user1_id,
+        Some(_) => return Err(ApiError::forbidden("Not a participant of this match")),
+        None => return Err(ApiError::not_found("Match not found")),
+    };
+
+    match_queries::unmatch(&pool, &match_id, &user_id, &other_user_id, reason).await?;
+
+    hub.publish(other_user_id, RealtimeEvent::Unmatch {
+        match_id: match_id.to_string(),
+    })
+    .await;
+
+    Ok(HttpResponse::Ok().json(StatusResponse {
+        status: "success".to_string(),
+        message: Some("Unmatched successfully".to_string()),
+    }))
+}
+
+/// Why a message could not be sent
+#[derive(Debug)]
+pub enum SendMessageError {
+    Empty,
+    TooLong,
+    NotParticipant,
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for SendMessageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendMessageError::Empty => write!(f, "Message cannot be empty"),
+            SendMessageError::TooLong => {
+                write!(f, "Message cannot exceed {} characters", MAX_MESSAGE_LENGTH)
+            }
+            SendMessageError::NotParticipant => write!(f, "Not a participant of this match"),
+            SendMessageError::Database(_) => write!(f, "Failed to send message"),
+        }
+    }
+}
+
+impl From<SendMessageError> for ApiError {
+    fn from(error: SendMessageError) -> Self {
+        match error {
+            SendMessageError::Database(e) => e.into(),
+            SendMessageError::NotParticipant => ApiError::forbidden(error.to_string()),
+            SendMessageError::Empty | SendMessageError::TooLong => ApiError::invalid("text", error.to_string()),
+        }
+    }
+}
+
+/// Validate, store and fan out a message from `sender_id`.
+/// Shared by the HTTP endpoint and the WebSocket
+pub async fn deliver_message(
+    pool: &PgPool,
+    hub: &dyn EventHub,
+    match_id: &Uuid,
+    sender_id: &Uuid,
+    text: &str,
+) -> Result<MessageRow, SendMessageError> {
+    // Validate input
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(SendMessageError::Empty);
+    }
+
+    if text.chars().count() > MAX_MESSAGE_LENGTH {
+        return Err(SendMessageError::TooLong);
+    }
+
+    let participants = match_queries::get_match_participants(pool, match_id)
+        .await
+        .map_err(SendMessageError::Database)?;
+
+    let Some((user1_id, user2_id)) = participants else {
+        return Err(SendMessageError::NotParticipant);
+    };
+
+    if *sender_id != user1_id && *sender_id != user2_id {
+        return Err(SendMessageError::NotParticipant);
+    }
+
+    let row = match_queries::insert_message(pool, match_id, sender_id, text)
+        .await
+        .map_err(SendMessageError::Database)?;
+
+    // Both sides get the event so the sender's other devices stay in sync
+    let message = to_message(&row);
+    for recipient in [user1_id, user2_id] {
+        hub.publish(
+            recipient,
+            RealtimeEvent::Message {
+                match_id: match_id.to_string(),
+                message: message.clone(),
+            },
+        )
+        .await;
+    }
+
+    Ok(row)
+}
+
+fn to_message(row: &MessageRow) -> Message {
+    Message {
+        id: row.id.to_string(),
+        sender_id: row.sender_id.to_string(),
+        text: row.text.clone(),
+        created_at: row.created_at.to_rfc3339(),
+        is_read: row.is_read,
+    }
 }
@@ -1,7 +1,11 @@
 pub mod auth;
+pub mod badges;
 pub mod feed;
+pub mod files;
 pub mod interactions;
 pub mod matches;
 pub mod profile;
 pub mod prompts;
+pub mod realtime;
+pub mod safety;
 pub mod user;
\ No newline at end of file
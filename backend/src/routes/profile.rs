@@ -1,83 +1,37 @@
 #![allow(unused)]
 
-use actix_web::{HttpRequest, HttpResponse, HttpMessage, Responder, web};
+use actix_web::{HttpRequest, HttpResponse, HttpMessage, web};
 use actix_multipart::form::{MultipartForm, json::Json as MpJson, tempfile::TempFile};
+use chrono::{NaiveDate, Utc};
 use serde::Deserialize;
 use sqlx::PgPool;
 use uuid::Uuid;
 use std::fs;
 use std::path::Path;
 
+use crate::error::ApiError;
+use crate::firebaseauth::CurrentUser;
 use crate::models::outputs::{UserProfile, UserImage, UserPrompt};
-use crate::models::inputs::{UpdateProfileRequest, UploadUrlRequest, DownloadRequest};
-use crate::jwtauth::Claims;
-use crate::models::outputs::{StatusResponse, FinalizeProfileResponse};
+use crate::models::inputs::{UpdateProfileRequest, UploadUrlRequest, DownloadRequest, ReorderImagesRequest, MIN_AGE, IMAGE_CONTENT_TYPES, MAX_IMAGE_BYTES};
+use crate::models::outputs::{StatusResponse, FinalizeProfileResponse, UploadUrlResponse};
 use crate::db::{profile_queries, prompt_queries, images_queries, user_queries};
-
-use firebase_auth::FirebaseUser;
 use crate::file_storage::{FileService, SignedUrlResponse, DownloadResponse};
 
 pub async fn get_profile(
     pool: web::Data<PgPool>, 
-    req: HttpRequest,
+    user: CurrentUser,
     file_service: web::Data<FileService>,
-) -> impl Responder {
-    let user: FirebaseUser = match req.extensions().get::<FirebaseUser>().cloned() {
-        Some(u) => u,
-        None => return HttpResponse::Unauthorized().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some("Unauthorized".to_string()),
-        })
-    };
-    
-    println!("User requested {}", user.sub);
-    println!("User email {:?}", user.email);
-
-    // Get user_id from database using email
-    let user_id = match user_queries::get_user_id_by_email(&pool, user.email.as_deref()).await {
-        Ok(Some(id)) => id,
-        Ok(None) => return HttpResponse::NotFound().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some("User not found".to_string()),
-        }),
-        Err(e) => return HttpResponse::InternalServerError().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some(format!("Database error: {}", e)),
-        })
-    };
+) -> Result<HttpResponse, ApiError> {
+    println!("User requested {}", user.firebase.sub);
+    println!("User email {:?}", user.firebase.email);
+
+    let user_id = user.id;
 
     // Get profile details (returns None if not found)
-    let profile_details = match profile_queries::get_profile(&pool, &user_id).await {
-        Ok(p) => Some(p),
-        Err(_) => None,
-    };
+    let profile_details = profile_queries::get_profile(&pool, &user_id).await.ok();
 
     // Get images and resolve download URLs
-    let user_images = match images_queries::get_user_images(&pool, &user_id).await {
-        Ok(rows) => {
-            let mut images = Vec::new();
-            for (id, key, order) in rows {
-                // Get presigned download URL for each image
-                let download_url = match file_service.download_file(&key).await {
-                    Ok(response) => response.download_url,
-                    Err(_) => key, // Fallback to key if download URL fails
-                };
-                images.push(UserImage {
-                    id: id.to_string(),
-                    url: download_url,
-                    order,
-                });
-            }
-            Some(images)
-
-            // rows.into_iter().map(|(id, key, order)| UserImage {
-            //     id: id.to_string(),
-            //     url: key,
-            //     order,
-            // }).collect() // error
-        },
-        Err(_) => None,
-    };
+    let user_images = load_user_images(&pool, &file_service, &user_id).await.ok();
 
     println!("User images: {:?}", user_images);
 
@@ -97,108 +51,248 @@ pub async fn get_profile(
         images: user_images,
         prompts: user_prompts,
         details: profile_details,
+        distance: None,
     };
 
-    HttpResponse::Ok().json(user_profile)
+    Ok(HttpResponse::Ok().json(user_profile))
 }
 
 pub async fn update_profile(
-    req: HttpRequest,
+    user: CurrentUser,
     body: web::Json<UpdateProfileRequest>,
     pool: web::Data<PgPool>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     println!("Profile: Updating - Name: {:?}", body.name);
 
-    // Get the user from Firebase auth
-    let user: FirebaseUser = match req.extensions().get::<FirebaseUser>().cloned() {
-        Some(u) => u,
-        None => {
-            return HttpResponse::Unauthorized().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some("Unauthorized".to_string()),
-            });
+    // Validate the birthdate ("1995-03-15") and that the user is old enough
+    if let Some(birthdate) = &body.birthdate {
+        let date = NaiveDate::parse_from_str(birthdate, "%Y-%m-%d")
+            .map_err(|_| ApiError::invalid("birthdate", "Birthdate must be in YYYY-MM-DD format"))?;
+
+        let today = Utc::now().date_naive();
+        if today.years_since(date).is_none_or(|age| (age as i32) < MIN_AGE) {
+            return Err(ApiError::invalid(
+                "birthdate",
+                format!("You must be at least {} years old", MIN_AGE),
+            ));
         }
-    };
+    }
 
-    // Get user_id from database using email
-    let user_id = match crate::db::user_queries::get_user_id_by_email(&pool, user.email.as_deref()).await {
-        Ok(Some(id)) => id,
-        Ok(None) => {
-            return HttpResponse::NotFound().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some("User not found".to_string()),
-            });
-        }
-        Err(e) => {
-            return HttpResponse::InternalServerError().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some(format!("Database error: {}", e)),
-            });
-        }
-    };
+    if body.location.is_some_and(|location| !location.is_valid()) {
+        return Err(ApiError::invalid(
+            "location",
+            "Location must have lat in [-90, 90] and lng in [-180, 180]",
+        ));
+    }
+
+    let user_id = user.id;
 
     // Create or update profile
-    match profile_queries::upsert_profile(&pool, &user_id, &body).await {
-        Ok(_) => {
-            HttpResponse::Ok().json(StatusResponse {
-                status: "success".to_string(),
-                message: Some("Profile updated successfully".to_string()),
-            })
-        }
-        Err(e) => {
-            println!("Failed to upsert profile: {:?}", e);
-            HttpResponse::InternalServerError().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some("Database error".to_string()),
-            })
-        }
+    profile_queries::upsert_profile(&pool, &user_id, &body).await?;
+
+    // The edit may have broken a finalization requirement
+    if let Err(e) = profile_queries::refresh_profile_complete(&pool, &user_id).await {
+        eprintln!("Failed to refresh profile completeness: {:?}", e);
     }
+
+    Ok(HttpResponse::Ok().json(StatusResponse {
+        status: "success".to_string(),
+        message: Some("Profile updated successfully".to_string()),
+    }))
 }
 
 // WORKING
 pub async fn get_upload_url(
     pool: web::Data<PgPool>, 
-    req: HttpRequest, 
+    user: CurrentUser, 
     body: web::Json<UploadUrlRequest>,
     file_service: web::Data<FileService>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     println!("Upload URL: {}", body.filename);
     println!("Upload URL: {}", body.content_type);
 
-    let Some(user) = req.extensions().get::<FirebaseUser>().cloned() else {
-        return HttpResponse::Unauthorized().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some("No authentication claims found".to_string()),
-        })
-    };
+    let user_id = user.id;
 
-    let user_id = match user_queries::get_user_id_by_email(&pool, user.email.as_deref()).await {
-        Ok(Some(id)) => id,
-        Ok(None) => return HttpResponse::NotFound().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some("User not found".to_string()),
-        }),
-        Err(e) => return HttpResponse::InternalServerError().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some(format!("Database error: {}", e)),
-        })
-    };
+    if !(0..images_queries::IMAGE_SLOTS).contains(&body.slot) {
+        return Err(ApiError::invalid(
+            "slot",
+            format!("Slot must be between 0 and {}", images_queries::IMAGE_SLOTS - 1),
+        ));
+    }
 
-    let (upload_url, key) = match file_service.upload_file_url(&body.filename, &body.content_type).await {
-        Ok(res) => (res.upload_url, res.key),
-        Err(e) => return HttpResponse::InternalServerError().json(SignedUrlResponse {
-            upload_url: "".to_string(),
-            key: "".to_string(),
-        })
-    };
+    if !IMAGE_CONTENT_TYPES.contains(&body.content_type.as_str()) {
+        return Err(ApiError::invalid(
+            "content_type",
+            format!("Content type must be one of {}", IMAGE_CONTENT_TYPES.join(", ")),
+        ));
+    }
 
-    // save the url and key need to change the db
-    images_queries::upload_profile_images(&pool, &user_id, &key).await;
+    let SignedUrlResponse { upload_url, key } = file_service
+        .upload_file_url(&body.filename, &body.content_type)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to create upload URL: {:?}", e))?;
 
-    HttpResponse::Ok().json(SignedUrlResponse {
+    // Pending until the client confirms the upload
+    let (image_id, replaced) = images_queries::upload_profile_images(&pool, &user_id, body.slot, &key).await?;
+    if let Some(replaced) = replaced {
+        delete_object(&file_service, &replaced).await;
+    }
+
+    Ok(HttpResponse::Ok().json(UploadUrlResponse {
         upload_url,
         key,
-    })
+        image_id: image_id.to_string(),
+    }))
+}
+
+/// POST /profile/images/{id}/confirm - Check that the photo reached storage and show it
+pub async fn confirm_upload(
+    pool: web::Data<PgPool>,
+    user: CurrentUser,
+    path: web::Path<Uuid>,
+    file_service: web::Data<FileService>,
+) -> Result<HttpResponse, ApiError> {
+    let image_id = path.into_inner();
+
+    let (key, mut order, status) = images_queries::get_image_upload(&pool, &user.id, &image_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Image not found"))?;
+
+    // Confirming twice is fine
+    if status != "ready" {
+        let Some(object) = file_service.head_file(&key).await? else {
+            return Err(ApiError::bad_request("The image has not been uploaded yet"));
+        };
+
+        let content_type = object.content_type.as_deref().unwrap_or_default();
+        let problem = if !IMAGE_CONTENT_TYPES.contains(&content_type) {
+            Some(format!("Content type must be one of {}", IMAGE_CONTENT_TYPES.join(", ")))
+        } else if object.size <= 0 {
+            Some("The uploaded image is empty".to_string())
+        } else if object.size > MAX_IMAGE_BYTES {
+            Some(format!("Image must be at most {} MB", MAX_IMAGE_BYTES / (1024 * 1024)))
+        } else {
+            None
+        };
+
+        if let Some(message) = problem {
+            // Unusable: drop it so the slot can be uploaded again
+            delete_object(&file_service, &key).await;
+            images_queries::delete_pending_image(&pool, &image_id).await?;
+            return Err(ApiError::invalid("image", message));
+        }
+
+        let Some(confirmed) = images_queries::mark_image_ready(&pool, &user.id, &image_id).await? else {
+            // Another request got there first: deleted (or reaped), or confirmed it
+            return match images_queries::get_image_upload(&pool, &user.id, &image_id).await? {
+                None => Err(ApiError::not_found("Image not found")),
+                Some(_) => Err(ApiError::conflict("The image is no longer pending")),
+            };
+        };
+        if let Some(replaced) = confirmed.replaced_key {
+            delete_object(&file_service, &replaced).await;
+        }
+        order = confirmed.slot;
+    }
+
+    let url = match file_service.download_file(&key).await {
+        Ok(response) => response.download_url,
+        Err(_) => key,
+    };
+
+    Ok(HttpResponse::Ok().json(UserImage {
+        id: image_id.to_string(),
+        url,
+        order,
+    }))
+}
+
+/// PUT /profile/images/order - Move the images to new slots, all at once
+pub async fn reorder_images(
+    pool: web::Data<PgPool>,
+    user: CurrentUser,
+    body: web::Json<ReorderImagesRequest>,
+    file_service: web::Data<FileService>,
+) -> Result<HttpResponse, ApiError> {
+    let image_ids = body
+        .image_ids
+        .iter()
+        .map(|id| Uuid::parse_str(id))
+        .collect::<Result<Vec<Uuid>, _>>()
+        .map_err(|_| ApiError::invalid("image_ids", "Invalid image ID"))?;
+
+    let mut unique = image_ids.clone();
+    unique.sort();
+    unique.dedup();
+    if unique.len() != image_ids.len() {
+        return Err(ApiError::invalid("image_ids", "Each image can only appear once"));
+    }
+
+    if !images_queries::reorder_images(&pool, &user.id, &image_ids).await? {
+        return Err(ApiError::invalid("image_ids", "The new order must list all of your images"));
+    }
+
+    Ok(HttpResponse::Ok().json(load_user_images(&pool, &file_service, &user.id).await?))
+}
+
+/// The ready images of a user with their viewable URLs
+async fn load_user_images(pool: &PgPool, file_service: &FileService, user_id: &Uuid) -> Result<Vec<UserImage>, sqlx::Error> {
+    let mut images = Vec::new();
+    for (id, key, order) in images_queries::get_user_images(pool, user_id).await? {
+        // Get presigned download URL for each image
+        let download_url = match file_service.download_file(&key).await {
+            Ok(response) => response.download_url,
+            Err(_) => key, // Fallback to key if download URL fails
+        };
+        images.push(UserImage {
+            id: id.to_string(),
+            url: download_url,
+            order,
+        });
+    }
+
+    Ok(images)
+}
+
+/// Best effort delete of a replaced or rejected object, the row is what matters
+async fn delete_object(file_service: &FileService, key: &str) {
+    if let Err(e) = file_service.delete_file(key).await {
+        eprintln!("Failed to delete object {}: {:?}", key, e);
+    }
+}
+
+/// DELETE /profile/images/{id} - Delete a photo and its stored object
+pub async fn delete_image(
+    pool: web::Data<PgPool>,
+    user: CurrentUser,
+    path: web::Path<Uuid>,
+    file_service: web::Data<FileService>,
+) -> Result<HttpResponse, ApiError> {
+    let image_id = path.into_inner();
+
+    let keys = images_queries::delete_profile_images(&pool, &user.id, &image_id).await?;
+    if keys.is_empty() {
+        return Err(ApiError::not_found("Image not found"));
+    }
+
+    for key in &keys {
+        delete_object(&file_service, key).await;
+    }
+
+    // Below the minimum the profile leaves the feed until finalized again
+    let message = if profile_queries::refresh_profile_complete(&pool, &user.id).await? {
+        format!(
+            "Image deleted, your profile needs {} photos to be shown again",
+            profile_queries::REQUIRED_IMAGES
+        )
+    } else {
+        "Image deleted successfully".to_string()
+    };
+
+    Ok(HttpResponse::Ok().json(StatusResponse {
+        status: "success".to_string(),
+        message: Some(message),
+    }))
 }
 
 // WORKING
@@ -207,141 +301,85 @@ pub async fn get_download_url(
     body: web::Json<DownloadRequest>, 
     pool: web::Data<PgPool>,
     file_service: web::Data<FileService>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     println!("Download URL: {}", body.key);
 
-    match file_service.download_file(&body.key).await {
-        Ok(response) => HttpResponse::Ok().json(response),
-        Err(_) => HttpResponse::NotFound().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some("File not found".to_string()),
-        })
-    }
-}
-
-pub async fn finalize_profile(req: HttpRequest, pool: web::Data<PgPool>) -> impl Responder {
+    let response = file_service
+        .download_file(&body.key)
+        .await
+        .map_err(|_| ApiError::not_found("File not found"))?;
 
-    let Some(claims) = req.extensions().get::<Claims>().cloned() else {
-        return HttpResponse::Unauthorized().json(FinalizeProfileResponse {
-            status: "error".to_string(),
-            message: Some("No authentication claims found".to_string()),
-            pending_actions: Some(vec!["No authentication claims found".to_string()]),
-        })
-    };
+    Ok(HttpResponse::Ok().json(response))
+}
 
-    let user_id: Uuid = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => id,
-        Err(_) => {
-            return HttpResponse::BadRequest().json(FinalizeProfileResponse {
-                status: "error".to_string(),
-                message: Some("Invalid user ID format".to_string()),
-                pending_actions: Some(vec!["Invalid user ID format".to_string()])
-            })
-        }
-    };
+pub async fn finalize_profile(user: CurrentUser, pool: web::Data<PgPool>) -> Result<HttpResponse, ApiError> {
+    let user_id = user.id;
 
     let mut pending: Vec<String> = Vec::new();
 
     // CHECK: all the 6 photos uploaded
-    let images_uploaded = match images_queries::count_images(&pool, &user_id).await {
-        Ok(count) => count,
-        Err(e) => {
-            println!("Failed to count images: {:?}", e);
-            return HttpResponse::InternalServerError().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some(e.to_string()),
-            });
-        }
-    };
+    let images_uploaded = images_queries::count_images(&pool, &user_id).await?;
 
-    if images_uploaded < 6 {
-        pending.push(format!("Upload {} more images", 6 - images_uploaded));
+    if images_uploaded < profile_queries::REQUIRED_IMAGES {
+        pending.push(format!(
+            "Upload {} more images",
+            profile_queries::REQUIRED_IMAGES - images_uploaded
+        ));
     }
 
     // CHECK: all the 3 prompts uploaded
-    let prompts_uploaded = match prompt_queries::count_prompts(&pool, &user_id).await {
-        Ok(count) => count,
-        Err(e) => {
-            println!("Failed to count prompts: {:?}", e);
-            return HttpResponse::InternalServerError().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some(e.to_string()),
-            });
-        }
-    };
-    if prompts_uploaded < 3 {
-        pending.push(format!("Upload {} more prompts", 3 - prompts_uploaded));
+    let prompts_uploaded = prompt_queries::count_prompts(&pool, &user_id).await?;
+    if prompts_uploaded < profile_queries::REQUIRED_PROMPTS {
+        pending.push(format!(
+            "Upload {} more prompts",
+            profile_queries::REQUIRED_PROMPTS - prompts_uploaded
+        ));
     }
 
     // CHECK: all profile details filled
     let missing_fields = match profile_queries::check_profile_attributes_filled(&pool, &user_id).await {
         Ok(count) => count,
-        Err(e) => {
-            println!("Failed to check profile attributes: {:?}", e);
-            return HttpResponse::InternalServerError().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some(e.to_string()),
-            });
+        Err(sqlx::Error::RowNotFound) => {
+            pending.push("Fill in your profile details".to_string());
+            0
         }
+        Err(e) => return Err(e.into()),
     };
     if missing_fields > 0 {
         pending.push(format!("Fill {} more profile details", missing_fields));
     }
 
     if pending.is_empty() {
-        HttpResponse::Ok().json(FinalizeProfileResponse {
+        profile_queries::set_profile_complete(&pool, &user_id).await?;
+
+        Ok(HttpResponse::Ok().json(FinalizeProfileResponse {
             status: "success".to_string(),
             message: Some("Profile finalized successfully".to_string()),
             pending_actions: Some(pending),
-        })
+        }))
     } else {
-        HttpResponse::BadRequest().json(FinalizeProfileResponse {
+        Ok(HttpResponse::BadRequest().json(FinalizeProfileResponse {
             status: "error".to_string(),
             message: Some("Profile not finalized".to_string()),
             pending_actions: Some(pending),
-        })
+        }))
     }
 }
 
 pub async fn delete_account(
-    req: HttpRequest,
+    user: CurrentUser,
     pool: web::Data<PgPool>,
-) -> impl Responder {
-    // Get user ID from claims
-    let claims = match req.extensions().get::<Claims>().cloned() {
-        Some(c) => c,
-        None => {
-            return HttpResponse::Unauthorized().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some("No authentication claims found".to_string()),
-            });
-        }
-    };
-
-    let user_id = match Uuid::parse_str(&claims.sub) {
-        Ok(id) => id,
-        Err(_) => {
-            return HttpResponse::BadRequest().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some("Invalid user ID format".to_string()),
-            });
-        }
-    };
+    file_service: web::Data<FileService>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = user.id;
 
-    // Delete user and profile and images and prompts
-    match profile_queries::delete_user(&pool, &user_id).await {
-        Ok(_) => {
-            HttpResponse::Ok().json(StatusResponse {
-                status: "success".to_string(),
-                message: Some("Account deleted successfully".to_string()),
-            })
-        }
-        Err(e) => {
-            println!("Failed to delete account: {:?}", e);
-            HttpResponse::InternalServerError().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some("Database error".to_string()),
-            })
-        }
+    // Delete user and profile and images and prompts, then the stored photos
+    for key in profile_queries::delete_user(&pool, &user_id).await? {
+        delete_object(&file_service, &key).await;
     }
+
+    Ok(HttpResponse::Ok().json(StatusResponse {
+        status: "success".to_string(),
+        message: Some("Account deleted successfully".to_string()),
+    }))
 }
@@ -0,0 +1,120 @@
+use actix_web::{FromRequest, HttpMessage, HttpRequest, HttpResponse, web};
+use actix_ws::{Message as WsMessage, Session};
+use firebase_auth::FirebaseAuth;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::firebaseauth::CurrentUser;
+use crate::firebaseauth::verifier::{verify_request, verify_token};
+use crate::realtime::{EventHub, RealtimeEvent};
+use crate::routes::matches::deliver_message;
+
+#[derive(Deserialize)]
+pub struct ConnectQuery {
+    // Firebase ID token, for clients that can't set the Authorization header
+    pub token: Option<String>,
+}
+
+/// Requests a client can send over the socket
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientEvent {
+    SendMessage { match_id: Uuid, text: String },
+}
+
+/// GET /api/v1/ws - Open the realtime connection of the current user
+pub async fn connect(
+    req: HttpRequest,
+    body: web::Payload,
+    query: web::Query<ConnectQuery>,
+    pool: web::Data<PgPool>,
+    hub: web::Data<dyn EventHub>,
+    firebase: web::Data<FirebaseAuth>,
+) -> Result<HttpResponse, actix_web::Error> {
+    // Same checks as FirebaseAuthMiddleware, with the query param as a fallback
+    let user = match &query.token {
+        Some(token) if req.headers().get("Authorization").is_none() => {
+            verify_token(token, firebase.get_ref())?
+        }
+        _ => verify_request(&req, firebase.get_ref())?,
+    };
+
+    // Then resolve the account like any other request
+    req.extensions_mut().insert(user);
+    let user_id = CurrentUser::extract(&req).await?.id;
+
+    let (response, mut session, mut stream) = actix_ws::handle(&req, body)?;
+    let mut events = hub.subscribe(user_id);
+
+    actix_web::rt::spawn(async move {
+        // The close reason sent by the client, echoed back when closing
+        let reason = loop {
+            tokio::select! {
+                Some(event) = events.recv() => {
+                    if send_event(&mut session, &event).await.is_err() {
+                        break None;
+                    }
+                }
+                msg = stream.recv() => match msg {
+                    Some(Ok(WsMessage::Text(text))) => {
+                        if let Err(error) = handle_client_event(&pool, hub.get_ref(), &user_id, &text).await
+                            && send_event(&mut session, &error).await.is_err()
+                        {
+                            break None;
+                        }
+                    }
+                    Some(Ok(WsMessage::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break None;
+                        }
+                    }
+                    Some(Ok(WsMessage::Close(reason))) => break reason,
+                    Some(Ok(_)) => {}
+                    Some(Err(_)) | None => break None,
+                }
+            }
+        };
+
+        drop(events);
+        hub.unsubscribe(user_id);
+
+        let _ = session.close(reason).await;
+    });
+
+    Ok(response)
+}
+
+async fn send_event(session: &mut Session, event: &RealtimeEvent) -> Result<(), actix_ws::Closed> {
+    match serde_json::to_string(event) {
+        Ok(json) => session.text(json).await,
+        Err(e) => {
+            eprintln!("Failed to serialize realtime event: {:?}", e);
+            Ok(())
+        }
+    }
+}
+
+/// Handle one request from the client; the error event is sent back on failure
+async fn handle_client_event(
+    pool: &PgPool,
+    hub: &dyn EventHub,
+    user_id: &Uuid,
+    text: &str,
+) -> Result<(), RealtimeEvent> {
+    let event: ClientEvent = serde_json::from_str(text).map_err(|e| RealtimeEvent::Error {
+        message: format!("Invalid request: {}", e),
+    })?;
+
+    match event {
+        ClientEvent::SendMessage { match_id, text } => {
+            // The new message reaches this socket through the hub like any other
+            deliver_message(pool, hub, &match_id, user_id, &text)
+                .await
+                .map(|_| ())
+                .map_err(|e| RealtimeEvent::Error {
+                    message: e.to_string(),
+                })
+        }
+    }
+}
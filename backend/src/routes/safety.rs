@@ -0,0 +1,146 @@
+use actix_web::{HttpResponse, web};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::safety_queries;
+use crate::error::ApiError;
+use crate::firebaseauth::CurrentUser;
+use crate::models::inputs::{BlockRequest, ReportRequest};
+use crate::models::outputs::{BlockedUsersResponse, ReportResponse, StatusResponse};
+
+const REPORT_TARGET_TYPES: [&str; 4] = ["PROFILE", "PHOTO", "PROMPT", "MESSAGE"];
+const REPORT_CATEGORIES: [&str; 7] = [
+    "SPAM",
+    "HARASSMENT",
+    "INAPPROPRIATE",
+    "FAKE_PROFILE",
+    "UNDERAGE",
+    "SCAM",
+    "OTHER",
+];
+const MAX_REPORT_DETAILS_LENGTH: usize = 1000;
+
+/// POST /blocks - Block a user
+pub async fn block_user(
+    pool: web::Data<PgPool>,
+    user: CurrentUser,
+    body: web::Json<BlockRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = user.id;
+
+    let blocked_id = Uuid::parse_str(&body.user_id)
+        .map_err(|_| ApiError::invalid("user_id", "Invalid user ID"))?;
+
+    if blocked_id == user_id {
+        return Err(ApiError::invalid("user_id", "You cannot block yourself"));
+    }
+
+    safety_queries::block_user(&pool, &user_id, &blocked_id)
+        .await
+        .map_err(ApiError::user_not_found)?;
+
+    Ok(HttpResponse::Ok().json(StatusResponse {
+        status: "success".to_string(),
+        message: Some("User blocked".to_string()),
+    }))
+}
+
+/// DELETE /blocks/{user_id} - Unblock a user
+pub async fn unblock_user(
+    pool: web::Data<PgPool>,
+    user: CurrentUser,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let blocked_id = path.into_inner();
+
+    if !safety_queries::unblock_user(&pool, &user.id, &blocked_id).await? {
+        return Err(ApiError::not_found("User is not blocked"));
+    }
+
+    Ok(HttpResponse::Ok().json(StatusResponse {
+        status: "success".to_string(),
+        message: Some("User unblocked".to_string()),
+    }))
+}
+
+/// GET /blocks - List the users the caller blocked
+pub async fn get_blocked_users(pool: web::Data<PgPool>, user: CurrentUser) -> Result<HttpResponse, ApiError> {
+    let ids = safety_queries::get_blocked_users(&pool, &user.id).await?;
+
+    Ok(HttpResponse::Ok().json(BlockedUsersResponse {
+        user_ids: ids.into_iter().map(|id| id.to_string()).collect(),
+    }))
+}
+
+/// POST /reports - Report a profile, photo, prompt or message
+pub async fn report(
+    pool: web::Data<PgPool>,
+    user: CurrentUser,
+    body: web::Json<ReportRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = user.id;
+
+    let reported_user_id = Uuid::parse_str(&body.reported_user_id)
+        .map_err(|_| ApiError::invalid("reported_user_id", "Invalid reported user ID"))?;
+
+    if reported_user_id == user_id {
+        return Err(ApiError::invalid("reported_user_id", "You cannot report yourself"));
+    }
+
+    // Validate input
+    let target_type = body.target_type.to_uppercase();
+    if !REPORT_TARGET_TYPES.contains(&target_type.as_str()) {
+        return Err(ApiError::invalid(
+            "target_type",
+            format!("Target type must be one of {}", REPORT_TARGET_TYPES.join(", ")),
+        ));
+    }
+
+    let category = body.category.to_uppercase();
+    if !REPORT_CATEGORIES.contains(&category.as_str()) {
+        return Err(ApiError::invalid(
+            "category",
+            format!("Category must be one of {}", REPORT_CATEGORIES.join(", ")),
+        ));
+    }
+
+    let details = body.details.as_deref().map(str::trim).filter(|d| !d.is_empty());
+    if details.is_some_and(|d| d.chars().count() > MAX_REPORT_DETAILS_LENGTH) {
+        return Err(ApiError::invalid(
+            "details",
+            format!("Details cannot exceed {} characters", MAX_REPORT_DETAILS_LENGTH),
+        ));
+    }
+
+    // Everything but a whole profile needs the id of the reported content
+    let target_id = if target_type == "PROFILE" {
+        None
+    } else {
+        let Some(Ok(target_id)) = body.target_id.as_deref().map(Uuid::parse_str) else {
+            return Err(ApiError::invalid("target_id", "A valid target ID is required"));
+        };
+
+        if !safety_queries::report_target_exists(&pool, &target_type, &target_id, &reported_user_id, &user_id).await? {
+            return Err(ApiError::not_found("Reported content not found"));
+        }
+
+        Some(target_id)
+    };
+
+    let report_id = safety_queries::create_report(
+        &pool,
+        &user_id,
+        &reported_user_id,
+        &target_type,
+        target_id.as_ref(),
+        &category,
+        details,
+    )
+    .await
+    .map_err(ApiError::user_not_found)?;
+
+    Ok(HttpResponse::Created().json(ReportResponse {
+        status: "success".to_string(),
+        report_id: report_id.to_string(),
+    }))
+}
@@ -1,13 +1,15 @@
 #![allow(unused)]
 
-use actix_web::{HttpMessage, HttpRequest, HttpResponse, Responder, web};
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, web};
 use serde_json::json;
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::db::user_queries;
+use crate::error::ApiError;
+use crate::firebaseauth::CurrentUser;
 use crate::jwtauth::Claims;
-use crate::models::inputs::Preferences;
+use crate::models::inputs::{MAX_AGE, MIN_AGE, Preferences};
 use crate::models::outputs::StatusResponse;
 
 use crate::models::inputs::CheckUserExistsRequest;
@@ -19,17 +21,13 @@ pub async fn create_user(
     pool: web::Data<PgPool>,
     req: HttpRequest,
     body: web::Json<CreateUserRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     // Try to get FirebaseUser from request extensions (set by middleware)
-    let firebase_user = match req.extensions().get::<FirebaseUser>().cloned() {
-        Some(u) => u,
-        None => {
-            return HttpResponse::Unauthorized().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some("No Firebase authentication found".to_string()),
-            });
-        }
-    };
+    let firebase_user = req
+        .extensions()
+        .get::<FirebaseUser>()
+        .cloned()
+        .ok_or(ApiError::Unauthorized)?;
 
     // Log Firebase user info
     println!("Firebase User ID: {}", firebase_user.user_id);
@@ -42,202 +40,134 @@ pub async fn create_user(
     // Validate that both phone and email are provided (required fields)
     let phone = match &body.phone {
         Some(p) if !p.is_empty() => p.as_str(),
-        _ => {
-            return HttpResponse::BadRequest().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some("Phone number is required".to_string()),
-            });
-        }
+        _ => return Err(ApiError::invalid("phone", "Phone number is required")),
     };
 
     let email = match &body.email {
         Some(e) if !e.is_empty() => e.as_str(),
-        _ => {
-            return HttpResponse::BadRequest().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some("Email is required".to_string()),
-            });
-        }
+        _ => return Err(ApiError::invalid("email", "Email is required")),
     };
 
     // Check if user already exists by email or phone
-    if let Ok(Some(existing_id)) = user_queries::check_user_exists(&pool, phone, email).await {
+    if let Some(existing_id) = user_queries::check_user_exists(&pool, phone, email).await? {
         println!("User exist");
-        return HttpResponse::Ok().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some(format!("User {} already exists", existing_id)),
-        });
+        return Err(ApiError::conflict(format!("User {} already exists", existing_id)));
     }
 
     // get the firebase user id
     let firebase_user_id = firebase_user.user_id.clone();
 
     // Create new user with both email and phone
-    match user_queries::create_user(&pool, phone, email, firebase_user_id).await {
-        Ok(user_id) => {
-            println!("User created");
-            HttpResponse::Ok().json(StatusResponse {
-                status: "success".to_string(),
-                message: Some(format!("User {} successfully created", user_id)),
-            })
-        }
-        Err(e) => {
-            eprintln!("Failed to create user: {}", e);
-            HttpResponse::InternalServerError().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some(format!("Failed to create user: {}", e)),
-            })
-        }
-    }
+    let user_id = user_queries::create_user(&pool, phone, email, firebase_user_id).await?;
+    println!("User created");
+
+    Ok(HttpResponse::Ok().json(StatusResponse {
+        status: "success".to_string(),
+        message: Some(format!("User {} successfully created", user_id)),
+    }))
 }
 
 pub async fn update_user_preference(
     pool: web::Data<PgPool>,
-    req: HttpRequest,
+    user: CurrentUser,
     body: web::Json<Preferences>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
+    // Validate the age range
+    if let Some(range) = &body.age_range {
+        if range.min < MIN_AGE {
+            return Err(ApiError::invalid("ageRange", format!("Minimum age must be at least {}", MIN_AGE)));
+        }
+
+        if range.max > MAX_AGE {
+            return Err(ApiError::invalid("ageRange", format!("Maximum age cannot exceed {}", MAX_AGE)));
+        }
+
+        if range.min > range.max {
+            return Err(ApiError::invalid("ageRange", "Minimum age cannot be greater than maximum age"));
+        }
+    }
+
     // Convert Preferences struct to JSON for storage
     let preferences_json = json!({
         "ageRange": body.age_range.as_ref().map(|r| json!({"min": r.min, "max": r.max})),
         "distanceMax": body.distance_max,
         "genderPreference": body.gender_preference,
         "ethnicityPreference": body.ethnicity_preference,
-        "religionPreference": body.religion_preference
+        "religionPreference": body.religion_preference,
+        "ethnicityDealbreaker": body.ethnicity_dealbreaker,
+        "religionDealbreaker": body.religion_dealbreaker
     });
 
     println!("User {:?}", preferences_json);
     
-    let user: FirebaseUser = match req.extensions().get::<FirebaseUser>() {
-        Some(user) => user.clone(),
-        None => {
-            return HttpResponse::Unauthorized().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some("Unauthorized".to_string()),
-            });
-        }
-    };
-    
-    println!("email {:?}", user.email);
-    
-    // Update preferences - will find user by email, then phone
-    match user_queries::update_user_preferences(
+    user_queries::update_user_preferences(
         &pool,
-        None, // No user_id from JWT - find by email/phone
-        user.email.as_deref(),
+        Some(&user.id),
+        None,
         None,
         preferences_json,
     )
     .await
-    {
-        Ok(_) => HttpResponse::Ok().json(StatusResponse {
-            status: "success".to_string(),
-            message: Some("User preferences updated successfully".to_string()),
-        }),
-        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some("User not found".to_string()),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some(format!("Failed to update user preferences: {}", e)),
-        }),
-    }
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => ApiError::not_found("User not found"),
+        e => e.into(),
+    })?;
+
+    Ok(HttpResponse::Ok().json(StatusResponse {
+        status: "success".to_string(),
+        message: Some("User preferences updated successfully".to_string()),
+    }))
 }
 
-pub async fn get_user_preferences(pool: web::Data<PgPool>, req: HttpRequest) -> impl Responder {
-    let user: FirebaseUser = match req.extensions().get::<FirebaseUser>().cloned() {
-        Some(user) => user,
-        None => {
-            return HttpResponse::Unauthorized().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some("Unauthorized".to_string()),
-            });
-        }
-    };
-
+pub async fn get_user_preferences(pool: web::Data<PgPool>, user: CurrentUser) -> Result<HttpResponse, ApiError> {
     // GOT THE PREFERENCES
-    let (user_id, prefereces) = match user_queries::get_user_with_preferences_by_identifier(
-        &pool,
-        user.email.as_deref(),
-        None,
-    )
-    .await
-    {
-        Ok(Some((uid, pref))) => (uid, pref),
-        Ok(None) => {
-            return HttpResponse::NotFound().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some("User not found".to_string()),
-            });
-        }
-        Err(e) => {
-            return HttpResponse::InternalServerError().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some(format!("Failed to find user: {}", e)),
-            });
-        }
-    };
+    let prefereces = user_queries::get_user_preferences(&pool, &user.id).await?;
 
     // Check if preferences exist
     let Some(prefs_json) = prefereces else {
-        return HttpResponse::BadRequest().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some("User has no preferences set".to_string()),
-        });
+        return Err(ApiError::bad_request("User has no preferences set"));
     };
 
     // convert to json
-    let preference_json: Preferences = match serde_json::from_value(prefs_json) {
-        Ok(p) => p,
-        Err(e) => {
-            return HttpResponse::InternalServerError().json(StatusResponse {
-                status: "error".to_string(),
-                message: Some(format!("Failed to parse preferences: {}", e)),
-            });
-        }
-    };
+    let preference_json: Preferences = serde_json::from_value(prefs_json)
+        .map_err(|e| anyhow::Error::new(e).context("Failed to parse stored preferences"))?;
 
     // println!("User {} Preferences: {:?}", user_id, preference_json);
 
-    return HttpResponse::Ok().json(preference_json);
+    Ok(HttpResponse::Ok().json(preference_json))
 }
 
 pub async fn check_user_exists(
     pool: web::Data<PgPool>,
     req: HttpRequest,
     body: web::Json<CheckUserExistsRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     // check for the user is present on the table or not
-    match user_queries::check_user_exists_optional(&pool, body.phone.clone(), body.email.clone())
-        .await
+    let response = match user_queries::check_user_exists_optional(&pool, body.phone.clone(), body.email.clone())
+        .await?
     {
-        Ok(Some(user_id)) => HttpResponse::Ok().json(StatusResponse {
+        Some(user_id) => StatusResponse {
             status: "exists".to_string(),
             message: Some(format!("User {} already exists", user_id)),
-        }),
-        Ok(None) => HttpResponse::Ok().json(StatusResponse {
+        },
+        None => StatusResponse {
             status: "not_found".to_string(),
             message: Some("User does not exist".to_string()),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some(format!("Failed to check user exists: {}", e)),
-        }),
-    }
+        },
+    };
+
+    Ok(HttpResponse::Ok().json(response))
 }
 
 pub async fn get_user(
     pool: web::Data<PgPool>,
     body: web::Json<CheckUserExistsRequest>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     // Check if at least one identifier is provided
     let (phone, email) = (body.phone.clone(), body.email.clone());
 
     if phone.is_none() && email.is_none() {
-        return HttpResponse::BadRequest().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some("At least one of phone or email is required".to_string()),
-        });
+        return Err(ApiError::bad_request("At least one of phone or email is required"));
     }
 
     // Try to find user by phone first, then email
@@ -247,46 +177,23 @@ pub async fn get_user(
         _ => unreachable!(), // Already handled above
     };
 
-    match user_result {
-        Ok(Some(user_id)) => {
-            // Found user ID, now fetch full user details
-            let uuid = match Uuid::parse_str(&user_id) {
-                Ok(id) => id,
-                Err(_) => {
-                    return HttpResponse::InternalServerError().json(StatusResponse {
-                        status: "error".to_string(),
-                        message: Some("Invalid user ID in database".to_string()),
-                    });
-                }
-            };
-
-            match user_queries::get_user(&pool, &uuid).await {
-                Ok(Some(user)) => HttpResponse::Ok().json(json!({
-                    "status": "success",
-                    "user": {
-                        "id": user.id.to_string(),
-                        "phone": user.phone,
-                        "email": user.email,
-                        "preferences": user.preferences
-                    }
-                })),
-                Ok(None) => HttpResponse::NotFound().json(StatusResponse {
-                    status: "not_found".to_string(),
-                    message: Some("User not found".to_string()),
-                }),
-                Err(e) => HttpResponse::InternalServerError().json(StatusResponse {
-                    status: "error".to_string(),
-                    message: Some(format!("Failed to get user: {}", e)),
-                }),
-            }
+    let user_id = user_result?.ok_or_else(|| ApiError::not_found("User not found"))?;
+
+    // Found user ID, now fetch full user details
+    let uuid = Uuid::parse_str(&user_id)
+        .map_err(|e| anyhow::Error::new(e).context("Invalid user ID in database"))?;
+
+    let user = user_queries::get_user(&pool, &uuid)
+        .await?
+        .ok_or_else(|| ApiError::not_found("User not found"))?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "status": "success",
+        "user": {
+            "id": user.id.to_string(),
+            "phone": user.phone,
+            "email": user.email,
+            "preferences": user.preferences
         }
-        Ok(None) => HttpResponse::NotFound().json(StatusResponse {
-            status: "not_found".to_string(),
-            message: Some("User not found".to_string()),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(StatusResponse {
-            status: "error".to_string(),
-            message: Some(format!("Failed to find user: {}", e)),
-        }),
-    }
+    })))
 }
@@ -44,7 +44,7 @@ export interface ProfileDetails {
   gender?: string;
   sexuality?: string;
   height?: number;
-  location?: string;
+  location?: string; // "lat,lng"
   job?: string;
   company?: string;
   school?: string;
@@ -57,6 +57,16 @@ export interface ProfileDetails {
   smokes?: string;
 }
 
+export interface GeoLocation {
+  lat: number;
+  lng: number;
+}
+
+// Profile fields sent on save: the location goes up as coordinates
+export type ProfileUpdate = Omit<Partial<ProfileDetails>, "location"> & {
+  location?: GeoLocation;
+};
+
 export interface UserProfile {
   id: string;
   images?: UserImage[];
@@ -112,7 +122,7 @@ export async function getMyProfile(): Promise<UserProfile> {
 }
 
 export async function updateProfile(
-  data: Partial<ProfileDetails>,
+  data: ProfileUpdate,
 ): Promise<StatusResponse> {
   return apiRequest<StatusResponse>("/api/v1/profile", {
     method: "POST",
@@ -129,6 +139,7 @@ export interface UploadUrlRequest {
 export interface SignedUrlResponse {
   upload_url: string;
   key: string;
+  image_id: string;
 }
 
 export interface DownloadUrlRequest {
@@ -139,13 +150,36 @@ export interface DownloadResponse {
   download_url: string;
 }
 
+// slot: 0 to 5, the photo there is replaced once the upload is confirmed
 export async function getUploadUrl(
   filename: string,
   contentType: string,
+  slot: number,
 ): Promise<SignedUrlResponse> {
   return apiRequest<SignedUrlResponse>("/api/v1/files/upload-url", {
     method: "POST",
-    body: { filename, content_type: contentType },
+    body: { filename, content_type: contentType, slot },
+  });
+}
+
+// Call once the PUT to upload_url succeeded, the image only shows after this
+export async function confirmUpload(imageId: string): Promise<UserImage> {
+  return apiRequest<UserImage>(`/api/v1/profile/images/${imageId}/confirm`, {
+    method: "POST",
+  });
+}
+
+export async function deleteImage(imageId: string): Promise<StatusResponse> {
+  return apiRequest<StatusResponse>(`/api/v1/profile/images/${imageId}`, {
+    method: "DELETE",
+  });
+}
+
+// All image ids in their new order, the first one goes in slot 0
+export async function reorderImages(imageIds: string[]): Promise<UserImage[]> {
+  return apiRequest<UserImage[]>("/api/v1/profile/images/order", {
+    method: "PUT",
+    body: { image_ids: imageIds },
   });
 }
 
@@ -257,12 +291,12 @@ export interface Interaction {
   created_at?: string;
 }
 
+// Interactions of the signed-in user to everyone else
 export async function getInteractionsToUser(
-  userId: string,
   action: "LIKE" | "PASS",
 ): Promise<Interaction[]> {
   return apiRequest<Interaction[]>(
-    `/api/v1/interact/to/${userId}?action=${action}`,
+    `/api/v1/interact/to?action=${action}`,
     {
       method: "POST",
     },
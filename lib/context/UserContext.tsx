@@ -5,6 +5,7 @@ import {
     updateProfile as updateProfileApi,
     updatePreferences as updatePreferencesApi,
     ProfileDetails,
+    ProfileUpdate,
     UserImage,
     UserPrompt,
     Preferences,
@@ -34,7 +35,7 @@ interface UserContextType extends UserState {
     refreshAll: () => Promise<void>;
 
     // Update functions
-    updateProfileData: (data: Partial<ProfileDetails>) => Promise<StatusResponse>;
+    updateProfileData: (data: ProfileUpdate) => Promise<StatusResponse>;
     updatePreferencesData: (prefs: Partial<Preferences>) => Promise<StatusResponse>;
 
     // Clear on logout
@@ -109,12 +110,16 @@ const [images, setImages] = useState< [] | null>(null);
     }, [fetchProfile, fetchPrompts]);
 
     // Update profile via API
-    const updateProfileData = useCallback(async (data: Partial<ProfileDetails>): Promise<StatusResponse> => {
+    const updateProfileData = useCallback(async (data: ProfileUpdate): Promise<StatusResponse> => {
         console.log('[UserContext] Updating profile:', data);
         const response = await updateProfileApi(data);
         if (response.status === 'success') {
-            // Update local state with new data
-            setProfile(prev => prev ? { ...prev, ...data } : data as ProfileDetails);
+            // Update local state with new data (the API returns the location as "lat,lng")
+            const { location, ...fields } = data;
+            const saved: Partial<ProfileDetails> = location
+                ? { ...fields, location: `${location.lat},${location.lng}` }
+                : fields;
+            setProfile(prev => prev ? { ...prev, ...saved } : saved as ProfileDetails);
         }
         return response;
     }, []);